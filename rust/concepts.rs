@@ -9,6 +9,7 @@ fn main() {
     const THREE_HOURS_IN_SECONDS: u32 = 60 * 60 * 3;
 
     //Shadowing
+    //cargo run --example shadowing
     let x = 5;
     let x = x + 1;
     {
@@ -55,6 +56,7 @@ fn main() {
     //循环 loop、while 和 for 
     //1.loop可以有返回值
     //2.循环标签：在多个循环之间消除歧义
+    //cargo run --example labeled_loops
     let mut count = 0;
     'counting_up: loop {
         println!("count = {count}");
@@ -0,0 +1,43 @@
+//struct.rs 关联函数与多个 impl 块
+//cargo run --example associated_functions
+
+#[derive(Debug)]
+struct Rectangle {
+    width: u32,
+    height: u32,
+}
+
+impl Rectangle {
+    //关联函数经常被用作返回一个结构体新实例的构造函数
+    fn square(size: u32) -> Self {
+        Self {
+            width: size,
+            height: size,
+        }
+    }
+}
+
+//每个结构体都允许拥有多个 impl 块
+impl Rectangle {
+    fn area(&self) -> u32 {
+        self.width * self.height
+    }
+}
+
+impl Rectangle {
+    fn can_hold(&self, other: &Rectangle) -> bool {
+        self.width > other.width && self.height > other.height
+    }
+}
+
+fn main() {
+    let sq = Rectangle::square(3);
+    println!("sq is {sq:?}");
+    println!("The area of sq is {} square pixels.", sq.area());
+
+    let rect = Rectangle {
+        width: 30,
+        height: 50,
+    };
+    println!("Can rect hold sq? {}", rect.can_hold(&sq));
+}
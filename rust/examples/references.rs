@@ -0,0 +1,37 @@
+//ownership.rs 只在栈上的数据：拷贝，以及引用的规则中“可以”的那几段
+//cargo run --example references
+
+fn main() {
+    //只在栈上的数据：拷贝
+    let x = 5;
+    let y = x;
+    println!("x = {x}, y = {y}");
+
+    //引用（references）
+    let s = String::from("hello");
+    let s_r = &s; //不可变
+    println!("s_r = {s_r}");
+
+    //一个引用的作用域从声明的地方开始一直持续到最后一次使用为止
+    let mut s = String::from("hello");
+    let r1 = &mut s;
+    println!("{}", r1);
+    let r2 = &mut s;
+    println!("{}", r2);
+
+    let mut s = String::from("hello");
+    {
+        let r1 = &mut s;
+        r1.push('!');
+    } // r1 在这里离开了作用域，所以我们完全可以创建一个新的引用
+    let r2 = &mut s;
+    println!("{r2}");
+
+    let mut s = String::from("hello");
+    let r1 = &s; // 没问题
+    let r2 = &s; // 没问题
+    println!("{r1} and {r2}");
+    // 此位置之后 r1 和 r2 不再使用
+    let r3 = &mut s; // 没问题
+    println!("{r3}");
+}
@@ -0,0 +1,33 @@
+//concepts.rs 变量、常量与隐藏
+//cargo run --example shadowing
+
+//常量可以在任何作用域中声明，包括全局作用域
+const THREE_HOURS_IN_SECONDS: u32 = 60 * 60 * 3;
+
+fn main() {
+    //variable immutable
+    let x = 5;
+    println!("The value of x is: {x}");
+
+    //variable mutable
+    let mut x = x;
+    x += 1;
+    println!("The value of x is: {x}");
+
+    //constants
+    println!("Three hours in seconds: {THREE_HOURS_IN_SECONDS}");
+
+    //Shadowing
+    let x = 5;
+    let x = x + 1;
+    {
+        let x = x * 2;
+        println!("The value of x in the inner scope is: {x}");
+    }
+    println!("The value of x is: {x}");
+
+    //隐藏时可以改变值的类型，并且复用这个名字
+    let spaces = "   ";
+    let spaces = spaces.len();
+    println!("spaces = {spaces}");
+}
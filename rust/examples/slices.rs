@@ -0,0 +1,58 @@
+//ownership.rs Slice
+//cargo run --example slices
+
+fn main() {
+    let s = String::from("hello world");
+    let hello = &s[0..5];
+    let world = &s[6..11];
+    println!("{hello} {world}");
+
+    //如果想要从索引 0 开始，可以不写两个点号之前的值
+    let s = String::from("hello");
+    let slice = &s[0..2];
+    println!("&s[0..2] = {slice}");
+    let slice = &s[..2];
+    println!("&s[..2] = {slice}");
+
+    //如果 slice 包含 String 的最后一个字节，也可以舍弃尾部的数字
+    let len = s.len();
+    let slice = &s[3..len];
+    println!("&s[3..len] = {slice}");
+    let slice = &s[3..];
+    println!("&s[3..] = {slice}");
+
+    //也可以同时舍弃这两个值来获取整个字符串的 slice
+    let slice = &s[0..len];
+    println!("&s[0..len] = {slice}");
+    let slice = &s[..];
+    println!("&s[..] = {slice}");
+
+    let s = String::from("hello world");
+    let word = first_word(&s);
+    println!("the first word is: {word}");
+
+    //字符串字面值就是 slice，可以直接传递给 first_word
+    let s = "Hello, world!";
+    let word = first_word(s);
+    println!("the first word is: {word}");
+
+    //其他类型的 slice
+    let a = [1, 2, 3, 4, 5];
+    let slice = &a[1..3];
+    assert_eq!(slice, &[2, 3]);
+    println!("&a[1..3] = {slice:?}");
+}
+
+//“字符串 slice” 的类型声明写作 &str
+//使用 &str 作为参数，&String 和 &str 都可以传进来
+fn first_word(s: &str) -> &str {
+    let bytes = s.as_bytes();
+
+    for (i, &item) in bytes.iter().enumerate() {
+        if item == b' ' {
+            return &s[0..i];
+        }
+    }
+
+    s
+}
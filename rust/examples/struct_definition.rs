@@ -0,0 +1,81 @@
+//struct.rs 定义并实例化结构体
+//cargo run --example struct_definition
+
+struct User {
+    active: bool,
+    username: String,
+    email: String,
+    sign_in_count: u64,
+}
+
+//元组结构体
+struct Color(i32, i32, i32);
+struct Point(i32, i32, i32);
+
+//类单元结构体
+struct AlwaysEqual;
+
+fn main() {
+    let mut user1 = User {
+        email: String::from("someone@example.com"),
+        username: String::from("someusername123"),
+        active: true,
+        sign_in_count: 1,
+    };
+
+    //整个实例必须是可变的，才能通过点号为字段赋值
+    user1.email = String::from("anotheremail@example.com");
+    println!("user1.email = {}", user1.email);
+
+    //使用结构体更新语法从其他实例创建实例
+    let user2 = User {
+        email: String::from("another@example.com"),
+        ..user1
+    };
+    //user1.username 被移动到了 user2，active 和 sign_in_count 是 Copy 的
+    println!(
+        "user2 = {} <{}>, active = {}, sign_in_count = {}",
+        user2.username, user2.email, user2.active, user2.sign_in_count
+    );
+    println!("user1.active = {}", user1.active);
+
+    let user3 = build_user(String::from("user3@example.com"), String::from("user3"));
+    let user4 =
+        build_user_field_init_shorthand(String::from("user4@example.com"), String::from("user4"));
+    for user in [&user3, &user4] {
+        println!(
+            "{} <{}>, active = {}, sign_in_count = {}",
+            user.username, user.email, user.active, user.sign_in_count
+        );
+    }
+
+    //元组结构体可以解构，也可以使用 . 后跟索引来访问单独的值
+    let black = Color(0, 0, 0);
+    let Color(r, g, b) = black;
+    println!("black = ({r}, {g}, {b})");
+    let origin = Point(0, 0, 0);
+    println!("origin = ({}, {}, {})", origin.0, origin.1, origin.2);
+
+    let _subject = AlwaysEqual;
+}
+
+//这里故意不用简写，和下面的 build_user_field_init_shorthand 对比
+#[allow(clippy::redundant_field_names)]
+fn build_user(email: String, username: String) -> User {
+    User {
+        email: email,
+        username: username,
+        active: true,
+        sign_in_count: 1,
+    }
+}
+
+//字段初始化简写语法（field init shorthand）
+fn build_user_field_init_shorthand(email: String, username: String) -> User {
+    User {
+        email,
+        username,
+        active: true,
+        sign_in_count: 1,
+    }
+}
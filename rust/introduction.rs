@@ -16,3 +16,7 @@
 //cargo new project [--help|--vcs]
 //Cargo.toml -- 配置文件
 //cargo [init | build [--release] | run | check]
+
+//workspace
+//笔记里的每个 fn main 小节都在 rust/examples/ 下有一个可运行的示例，见 rust/src/lib.rs
+//cargo run --example <name>
@@ -20,6 +20,7 @@ fn main() {
 
 
     //只在栈上的数据：拷贝
+    //cargo run --example references
     let x = 5;
     let y = x;
 
@@ -127,6 +128,7 @@ fn main() {
       
     //Slice
     //slice 允许你引用集合中一段连续的元素序列，而不用引用整个集合。
+    //cargo run --example slices
     //slice 是一种引用，所以它没有所有权。
     //字符串 slice（string slice）是 String 中一部分值的引用，它看起来像这样：
     //  let s = String::from("hello world");
@@ -139,7 +141,7 @@ fn main() {
     let s = String::from("hello");
 
     let slice = &s[0..2];
-    let slice = &s[..2]
+    let slice = &s[..2];
     //依此类推，如果 slice 包含 String 的最后一个字节，也可以舍弃尾部的数字。
     let s = String::from("hello");
 
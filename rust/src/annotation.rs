@@ -0,0 +1,117 @@
+//! 作者自己的批注
+//!
+//! 笔记大多转述自《Rust 程序设计语言》，作者自己的理解用 `批注：` 标在句尾：
+//!
+//! ```text
+//! //所有权规则    --      批注：有点想cpp的uniqur_ptr
+//! ```
+//!
+//! 这里把它们和所在的位置、附近的代码一起抽出来，生成复习用的报告和抽认卡。
+
+use crate::snippet;
+
+/// 一条批注
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub file: String,
+    /// 行号，从 1 开始
+    pub line: usize,
+    /// 被批注的原文，即 `批注` 前面的部分
+    pub subject: String,
+    /// 批注的内容
+    pub note: String,
+    /// 批注之后最近的一段代码：(第一行的行号, 代码)
+    pub code: Option<(usize, String)>,
+}
+
+const MARKER: &str = "批注";
+
+/// 去掉句尾的批注和前面的 `--`，剩下被批注的原文；没有批注时原样返回。
+pub fn strip(text: &str) -> &str {
+    match text.find(MARKER) {
+        Some(at) => text[..at].trim_end().trim_end_matches('-').trim_end(),
+        None => text,
+    }
+}
+
+/// 从 `line`（下标）往后找第一段代码：注释外的连续非空行，或者注释里的代码片段。
+fn next_code(lines: &[&str], blocks: &[snippet::Block], from: usize) -> Option<(usize, String)> {
+    for i in from..lines.len() {
+        if let Some(block) = blocks.iter().find(|block| block.line == i + 1) {
+            return Some((block.line, block.code.trim_end().to_string()));
+        }
+        if snippet::comment_text(lines[i]).is_none() && !lines[i].trim().is_empty() {
+            let code: Vec<&str> = lines[i..]
+                .iter()
+                .take_while(|line| snippet::comment_text(line).is_none() && !line.trim().is_empty())
+                .map(|line| line.trim())
+                .collect();
+            return Some((i + 1, code.join("\n")));
+        }
+    }
+    None
+}
+
+/// 找出一篇笔记里所有的批注。
+pub fn extract(file: &str, source: &str) -> Vec<Annotation> {
+    let lines: Vec<&str> = source.lines().collect();
+    let blocks = snippet::blocks(source);
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let text = snippet::comment_text(line)?;
+            let at = text.find(MARKER)?;
+            let subject = strip(text).trim_start();
+            let note = text[at + MARKER.len()..]
+                .trim_start_matches(['：', ':'])
+                .trim();
+            Some(Annotation {
+                file: file.to_string(),
+                line: i + 1,
+                subject: subject.to_string(),
+                note: note.to_string(),
+                code: next_code(&lines, &blocks, i + 1),
+            })
+        })
+        .collect()
+}
+
+/// 复习报告（Markdown）：按文件分组，每条批注列出原文、批注和附近的代码。
+pub fn report(annotations: &[Annotation]) -> String {
+    let mut md = format!("# 批注\n\n共 {} 条。\n", annotations.len());
+    let mut file = "";
+    for a in annotations {
+        if a.file != file {
+            file = &a.file;
+            md.push_str(&format!("\n## {file}\n"));
+        }
+        md.push_str(&format!("\n### {}:{}\n\n", a.file, a.line));
+        md.push_str(&format!("> {}\n\n**批注：** {}\n", a.subject, a.note));
+        if let Some((line, code)) = &a.code {
+            md.push_str(&format!(
+                "\n附近的代码（第 {line} 行）：\n\n```rust\n{code}\n```\n"
+            ));
+        }
+    }
+    md
+}
+
+/// 抽认卡，每行一张，正面和背面用制表符分开，可以直接导入 Anki。
+///
+/// 正面是被批注的原文，背面是批注和出处；字段里的换行写成 `<br>`。
+pub fn flashcards(annotations: &[Annotation]) -> String {
+    let field = |text: &str| text.replace('\t', " ").replace('\n', "<br>");
+    annotations
+        .iter()
+        .map(|a| {
+            format!(
+                "{}<br>（我的理解是？）\t{}<br>— {}:{}\n",
+                field(&a.subject),
+                field(&a.note),
+                a.file,
+                a.line
+            )
+        })
+        .collect()
+}
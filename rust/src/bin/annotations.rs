@@ -0,0 +1,33 @@
+//把笔记里的批注单独抽出来复习。
+//
+//cargo run --bin annotations            复习报告（Markdown）
+//cargo run --bin annotations -- cards   抽认卡（制表符分隔，可导入 Anki）
+
+use std::process::ExitCode;
+
+use rust_notes::{annotation, read_notes};
+
+fn main() -> ExitCode {
+    let mode = std::env::args().nth(1);
+    let notes = match read_notes() {
+        Ok(notes) => notes,
+        Err(e) => {
+            eprintln!("failed to read notes: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let annotations: Vec<_> = notes
+        .iter()
+        .flat_map(|(file, source)| annotation::extract(file, source))
+        .collect();
+
+    match mode.as_deref() {
+        None | Some("report") => print!("{}", annotation::report(&annotations)),
+        Some("cards") => print!("{}", annotation::flashcards(&annotations)),
+        Some(other) => {
+            eprintln!("unknown mode `{other}`, expected `report` or `cards`");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,32 @@
+//把笔记渲染成可以在浏览器里看的 HTML，说明和代码左右对照。
+//
+//cargo run --bin book              写到 target/book/
+//cargo run --bin book -- <目录>    写到指定目录
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use rust_notes::{book, notes_dir, read_chapter_map, read_notes};
+
+fn main() -> ExitCode {
+    let out = std::env::args_os()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| notes_dir().parent().unwrap().join("target/book"));
+
+    let written = read_notes()
+        .and_then(|notes| Ok((notes, read_chapter_map()?)))
+        .and_then(|(notes, chapters)| book::write(&out, &notes, &chapters));
+    match written {
+        Ok(files) => {
+            for file in files {
+                println!("wrote {}", file.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("failed to render the book: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
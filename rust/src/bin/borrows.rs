@@ -0,0 +1,47 @@
+//按行画出每个引用从创建到最后一次使用的区间，标出共享借用和可变借用的冲突。
+//文件里没有函数时，把整个文件当作一段函数体。
+//
+//cargo run --bin borrows -- <文件> [--fn <函数名>]
+//cargo run --bin borrows -- rust/examples/references.rs
+
+use std::process::ExitCode;
+
+use rust_notes::borrows;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let (mut path, mut function) = (None, String::from("main"));
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fn" => function = args.next().unwrap_or_default(),
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("usage: borrows <file> [--fn <name>]");
+        return ExitCode::FAILURE;
+    };
+
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("failed to read {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let chart = if source.contains("fn ") {
+        borrows::analyze(&source, &function)
+    } else {
+        borrows::analyze_body(&source)
+    };
+    match chart {
+        Ok(chart) => {
+            print!("{chart}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//把笔记注释里的代码片段抽到 snippets.md，让 cargo test 以 doctest 的形式运行它们。
+//
+//cargo run --bin extract_snippets            重新生成
+//cargo run --bin extract_snippets -- --check 只检查 snippets.md 是否是最新的
+
+use std::process::ExitCode;
+
+use rust_notes::{notes_dir, read_notes, snippet};
+
+fn main() -> ExitCode {
+    let check = std::env::args().any(|arg| arg == "--check");
+    let notes = match read_notes() {
+        Ok(notes) => notes,
+        Err(e) => {
+            eprintln!("failed to read notes: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let generated = snippet::doctests(&notes);
+    let path = notes_dir().join("snippets.md");
+
+    if check {
+        let current = std::fs::read_to_string(&path).unwrap_or_default();
+        if current != generated {
+            eprintln!(
+                "{} is out of date, run `cargo run --bin extract_snippets`",
+                path.display()
+            );
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    if let Err(e) = std::fs::write(&path, generated) {
+        eprintln!("failed to write {}: {e}", path.display());
+        return ExitCode::FAILURE;
+    }
+    println!("wrote {}", path.display());
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,47 @@
+//把 ownership.rs 里关于内存的说法在运行时量一遍：String 的指针/长度/容量、slice 的指针/长度、
+//字符串字面值在静态区、数组和元组整个放在栈上。
+//
+//cargo run --bin layout
+
+use rust_notes::layout::{self, Layout};
+
+fn show(code: &str, layout: Layout) {
+    println!("{code}");
+    println!("  {layout}");
+}
+
+fn main() {
+    let s1 = String::from("hello");
+    show(r#"let s1 = String::from("hello");"#, layout::inspect(&s1));
+
+    let s2 = s1.clone();
+    show("let s2 = s1.clone();", layout::inspect(&s2));
+
+    let mut s = String::with_capacity(16);
+    s.push_str("hello world");
+    show(
+        "let mut s = String::with_capacity(16); s.push_str(...);",
+        layout::inspect(&s),
+    );
+
+    let hello = &s[0..5];
+    show("let hello = &s[0..5];", layout::inspect(&hello));
+
+    let literal = "Hello, world!";
+    show(
+        r#"let literal = "Hello, world!";"#,
+        layout::inspect(&literal),
+    );
+
+    let a = [1, 2, 3, 4, 5];
+    show("let a = [1, 2, 3, 4, 5];", layout::inspect(&a));
+
+    let slice = &a[1..3];
+    show("let slice = &a[1..3];", layout::inspect(&slice));
+
+    let tuple = (500, String::from("tup"));
+    show(
+        r#"let tuple = (500, String::from("tup"));"#,
+        layout::inspect(&tuple),
+    );
+}
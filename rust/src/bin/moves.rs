@@ -0,0 +1,42 @@
+//逐条语句显示结构体变量的哪些字段被移走了、哪些还能用。
+//
+//cargo run --bin moves -- <文件> [--fn <函数名>] [--only <变量名>]
+//cargo run --bin moves -- rust/examples/struct_definition.rs --only user1
+
+use std::process::ExitCode;
+
+use rust_notes::moves;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let (mut path, mut function, mut only) = (None, String::from("main"), None);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fn" => function = args.next().unwrap_or_default(),
+            "--only" => only = args.next(),
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("usage: moves <file> [--fn <name>] [--only <binding>]");
+        return ExitCode::FAILURE;
+    };
+
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("failed to read {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match moves::analyze(&source, &function) {
+        Ok(timeline) => {
+            print!("{}", timeline.render(only.as_deref()));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
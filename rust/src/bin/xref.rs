@@ -0,0 +1,35 @@
+//检查笔记里的交叉引用：引用的别的笔记的小节、书的章节（chapters.txt）是否都找得到。
+//
+//cargo run --bin xref    列出找不到的引用，有的话以失败退出
+
+use std::process::ExitCode;
+
+use rust_notes::book;
+use rust_notes::xref::{self, Resolver};
+use rust_notes::{read_chapter_map, read_notes};
+
+fn main() -> ExitCode {
+    let (notes, chapters) = match read_notes().and_then(|notes| Ok((notes, read_chapter_map()?))) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("failed to read notes: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let pages: Vec<_> = notes
+        .iter()
+        .map(|(file, source)| book::page(file, source))
+        .collect();
+    let resolver = Resolver::new(&pages, &chapters);
+
+    let dangling = xref::check(&notes, &resolver);
+    for reference in &dangling {
+        println!("{reference}");
+    }
+    if dangling.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("{} dangling reference(s)", dangling.len());
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,395 @@
+//! 把笔记渲染成 HTML：左边是说明文字，右边是代码
+//!
+//! 笔记中的注释行是说明，其余的行是代码；注释里的代码片段（见 [`snippet::blocks`]）也放在代码一侧，
+//! 并标出它应有的结果。每篇笔记一页，页首是按小标题生成的目录。说明里的交叉引用（见 [`xref`]）
+//! 渲染成链接。
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::annotation;
+use crate::highlight;
+use crate::snippet::{self, Block, Expect};
+use crate::xref::{self, ChapterMap, Resolver};
+
+/// 右侧的一段代码
+#[derive(Debug)]
+pub enum Code {
+    /// 笔记里真正的代码，`line` 是第一行的行号
+    Source { line: usize, text: String },
+    /// 注释里的代码片段
+    Snippet(Block),
+}
+
+/// 页面中的一行：一段说明和紧随其后的代码。
+#[derive(Debug, Default)]
+pub struct Row {
+    /// 以小标题开头时的标题
+    pub heading: Option<String>,
+    /// 说明文字，每个元素是一行注释；空字符串表示分段
+    pub prose: Vec<String>,
+    pub code: Vec<Code>,
+}
+
+#[derive(Debug)]
+pub struct Page {
+    pub file: String,
+    pub title: String,
+    pub rows: Vec<Row>,
+}
+
+impl Page {
+    /// 页面的文件名，例如 `struct.html`
+    pub fn html_name(&self) -> String {
+        format!("{}.html", self.file.trim_end_matches(".rs"))
+    }
+
+    /// 目录：(锚点, 标题)
+    pub fn toc(&self) -> Vec<(String, &str)> {
+        self.rows
+            .iter()
+            .filter_map(|row| row.heading.as_deref())
+            .enumerate()
+            .map(|(i, heading)| (anchor(i), heading))
+            .collect()
+    }
+}
+
+fn anchor(index: usize) -> String {
+    format!("s{}", index + 1)
+}
+
+/// 各篇笔记的标题
+pub fn title(file: &str) -> &str {
+    match file {
+        "introduction.rs" => "安装与工具",
+        "concepts.rs" => "常见编程概念",
+        "ownership.rs" => "认识所有权",
+        "struct.rs" => "结构体",
+        _ => file,
+    }
+}
+
+/// 一段说明的第一行如果足够短、不像一句完整的话，也不是命令或文件名，就当作小标题。
+/// 句尾的批注不算在内，小标题本身照样带着它。
+fn is_heading(text: &str) -> bool {
+    let text = annotation::strip(text).trim_end();
+    !text.is_empty()
+        && !text.starts_with(char::is_whitespace)
+        && text.chars().count() <= 24
+        && !text.ends_with(['。', '，', ',', '：', ':', '；', ';'])
+        && !["cargo", "rustc", "rustup", "$"]
+            .iter()
+            .any(|p| text.starts_with(p))
+        && !text.ends_with(".rs")
+}
+
+/// 把一篇笔记拆成说明和代码交替的行。
+pub fn page(file: &str, source: &str) -> Page {
+    let lines: Vec<&str> = source.lines().collect();
+    let blocks = snippet::blocks(source);
+    let mut rows = vec![Row::default()];
+    //上一行是不是说明，用来判断一段说明从哪里开始
+    let mut in_prose = false;
+    //紧跟在片段后面的说明是在解释片段，不当作小标题
+    let mut after_snippet = false;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let number = i + 1;
+        if let Some(block) = blocks.iter().find(|block| block.line == number) {
+            rows.last_mut()
+                .unwrap()
+                .code
+                .push(Code::Snippet(block.clone()));
+            i = block.end_line();
+            in_prose = false;
+            after_snippet = true;
+            continue;
+        }
+        i += 1;
+
+        let line = lines[number - 1];
+        match snippet::comment_text(line) {
+            Some(text) if text.trim().is_empty() => {
+                let row = rows.last_mut().unwrap();
+                if in_prose {
+                    row.prose.push(String::new());
+                }
+                in_prose = false;
+            }
+            Some(text) => {
+                let paragraph_start = !in_prose && !after_snippet;
+                let row = rows.last_mut().unwrap();
+                if paragraph_start && is_heading(text) {
+                    rows.push(Row {
+                        heading: Some(text.trim().to_string()),
+                        ..Row::default()
+                    });
+                } else {
+                    if !row.code.is_empty() {
+                        rows.push(Row::default());
+                    }
+                    rows.last_mut()
+                        .unwrap()
+                        .prose
+                        .push(text.trim_end().to_string());
+                }
+                in_prose = true;
+                after_snippet = false;
+            }
+            None => {
+                in_prose = false;
+                after_snippet = false;
+                let row = rows.last_mut().unwrap();
+                match row.code.last_mut() {
+                    Some(Code::Source { text, .. }) => {
+                        text.push('\n');
+                        text.push_str(line.trim_end());
+                    }
+                    _ if line.trim().is_empty() => {}
+                    _ => row.code.push(Code::Source {
+                        line: number,
+                        text: line.trim_end().to_string(),
+                    }),
+                }
+            }
+        }
+    }
+
+    for row in &mut rows {
+        while row.prose.last().is_some_and(String::is_empty) {
+            row.prose.pop();
+        }
+        for code in &mut row.code {
+            if let Code::Source { text, .. } = code {
+                text.truncate(text.trim_end().len());
+            }
+        }
+    }
+    rows.retain(|row| row.heading.is_some() || !row.prose.is_empty() || !row.code.is_empty());
+
+    Page {
+        file: file.to_string(),
+        title: title(file).to_string(),
+        rows,
+    }
+}
+
+/// 把文字中的引用换成链接，解析不了的引用标成 `dangling`。
+fn linked(text: &str, resolver: &Resolver) -> String {
+    let mut html = String::new();
+    let mut at = 0;
+    for reference in xref::find(text) {
+        html.push_str(&highlight::escape(&text[at..reference.range.start]));
+        let label = highlight::escape(&text[reference.range.clone()]);
+        match resolver.resolve(&reference.target) {
+            Some(link) => html.push_str(&format!(
+                "<a href=\"{}\" title=\"{}\">{label}</a>",
+                highlight::escape(&link.href),
+                highlight::escape(&link.title)
+            )),
+            None => html.push_str(&format!("<span class=\"dangling\">{label}</span>")),
+        }
+        at = reference.range.end;
+    }
+    html.push_str(&highlight::escape(&text[at..]));
+    html
+}
+
+/// 一行说明文字的 HTML。`批注` 开始到行尾是作者自己的理解，单独标出来。
+pub fn prose_html(text: &str, resolver: &Resolver) -> String {
+    match text.find("批注") {
+        Some(at) => format!(
+            "{}<span class=\"annotation\">{}</span>",
+            linked(&text[..at], resolver),
+            linked(&text[at..], resolver)
+        ),
+        None => linked(text, resolver),
+    }
+}
+
+fn expect_label(expect: &Expect) -> String {
+    match expect {
+        Expect::Compile => String::from("可以编译"),
+        Expect::Fail(None) => String::from("不能编译"),
+        Expect::Fail(Some(code)) => format!("不能编译 {code}"),
+        Expect::Panic => String::from("运行时 panic"),
+        Expect::Ignore => String::from("伪代码"),
+    }
+}
+
+fn render_row(html: &mut String, row: &Row, heading_index: &mut usize, resolver: &Resolver) {
+    if let Some(heading) = &row.heading {
+        html.push_str(&format!(
+            "<h2 id=\"{}\">{}</h2>\n",
+            anchor(*heading_index),
+            prose_html(heading, resolver)
+        ));
+        *heading_index += 1;
+    }
+    if row.prose.is_empty() && row.code.is_empty() {
+        return;
+    }
+    html.push_str("<section class=\"row\">\n<div class=\"prose\">\n");
+    for text in &row.prose {
+        if text.is_empty() {
+            html.push_str("<br>\n");
+            continue;
+        }
+        let depth = text.len() - text.trim_start().len();
+        html.push_str(&format!(
+            "<p style=\"padding-left: {}em\">{}</p>\n",
+            depth as f32 / 2.0,
+            prose_html(text.trim_start(), resolver)
+        ));
+    }
+    html.push_str("</div>\n<div class=\"code\">\n");
+    for code in &row.code {
+        match code {
+            Code::Source { line, text } => html.push_str(&format!(
+                "<pre data-line=\"{line}\">{}</pre>\n",
+                highlight::rust(text)
+            )),
+            Code::Snippet(block) => {
+                let class = match block.expect {
+                    Expect::Fail(_) => "snippet fail",
+                    Expect::Panic => "snippet panic",
+                    Expect::Ignore => "snippet ignore",
+                    Expect::Compile => "snippet",
+                };
+                html.push_str(&format!(
+                    "<figure class=\"{class}\"><figcaption>{}</figcaption><pre data-line=\"{}\">{}</pre></figure>\n",
+                    expect_label(&block.expect),
+                    block.line,
+                    highlight::rust(block.code.trim_end())
+                ));
+            }
+        }
+    }
+    html.push_str("</div>\n</section>\n");
+}
+
+fn nav(pages: &[Page], current: Option<&Page>) -> String {
+    let mut html = String::from("<nav class=\"files\"><a href=\"index.html\">目录</a>");
+    for page in pages {
+        let class = if current.is_some_and(|c| c.file == page.file) {
+            " class=\"current\""
+        } else {
+            ""
+        };
+        html.push_str(&format!(
+            " · <a href=\"{}\"{class}>{}</a>",
+            page.html_name(),
+            highlight::escape(&page.title)
+        ));
+    }
+    html.push_str("</nav>\n");
+    html
+}
+
+fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n{body}</body>\n</html>\n",
+        highlight::escape(title)
+    )
+}
+
+fn toc_html(page: &Page, resolver: &Resolver) -> String {
+    let mut html = String::from("<ol class=\"toc\">\n");
+    for (anchor, heading) in page.toc() {
+        html.push_str(&format!(
+            "<li><a href=\"{}#{anchor}\">{}</a></li>\n",
+            page.html_name(),
+            prose_html(heading, resolver)
+        ));
+    }
+    html.push_str("</ol>\n");
+    html
+}
+
+/// 一篇笔记的页面。
+pub fn render_page(page: &Page, resolver: &Resolver) -> String {
+    let mut body = nav(resolver.pages(), Some(page));
+    body.push_str(&format!(
+        "<h1>{} <small>{}</small></h1>\n",
+        highlight::escape(&page.title),
+        highlight::escape(&page.file)
+    ));
+    body.push_str(&toc_html(page, resolver));
+    let mut heading_index = 0;
+    for row in &page.rows {
+        render_row(&mut body, row, &mut heading_index, resolver);
+    }
+    document(&page.title, &body)
+}
+
+/// 首页：所有笔记和它们的目录。
+pub fn render_index(resolver: &Resolver) -> String {
+    let pages = resolver.pages();
+    let mut body = nav(pages, None);
+    body.push_str("<h1>Rust 学习笔记</h1>\n");
+    for page in pages {
+        body.push_str(&format!(
+            "<h2><a href=\"{}\">{}</a> <small>{}</small></h2>\n",
+            page.html_name(),
+            highlight::escape(&page.title),
+            highlight::escape(&page.file)
+        ));
+        body.push_str(&toc_html(page, resolver));
+    }
+    document("Rust 学习笔记", &body)
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 0 auto; max-width: 90em; padding: 1em; }
+nav.files a.current { font-weight: bold; }
+h1 small, h2 small { color: #888; font-weight: normal; font-size: 60%; }
+.row { display: grid; grid-template-columns: 2fr 3fr; gap: 1em; border-top: 1px solid #eee; padding: .5em 0; }
+.prose p { margin: .2em 0; }
+.annotation { background: #fff4c2; }
+.dangling { text-decoration: underline wavy #c33; }
+pre { background: #f6f8fa; padding: .5em; margin: 0 0 .5em; overflow-x: auto; }
+figure { margin: 0; }
+figcaption { font-size: 80%; color: #2a7; }
+.fail figcaption { color: #c33; }
+.panic figcaption { color: #c70; }
+.ignore figcaption { color: #888; }
+.keyword { color: #a626a4; }
+.type { color: #c18401; }
+.macro { color: #4078f2; }
+.string { color: #50a14f; }
+.number, .lifetime { color: #986801; }
+.comment { color: #a0a1a7; font-style: italic; }
+";
+
+/// 渲染所有笔记，写到 `out` 目录下，返回写出的文件。
+///
+/// `notes` 是 (文件名, 内容) 的列表，见 [`crate::read_notes`]；`chapters` 用来把书的章节引用渲染成链接。
+pub fn write(
+    out: &Path,
+    notes: &[(&str, String)],
+    chapters: &ChapterMap,
+) -> io::Result<Vec<PathBuf>> {
+    let pages: Vec<Page> = notes
+        .iter()
+        .map(|(file, source)| page(file, source))
+        .collect();
+    let resolver = Resolver::new(&pages, chapters);
+    std::fs::create_dir_all(out)?;
+
+    let mut written = Vec::new();
+    let mut emit = |name: &str, contents: String| -> io::Result<()> {
+        let path = out.join(name);
+        std::fs::write(&path, contents)?;
+        written.push(path);
+        Ok(())
+    };
+    emit("style.css", STYLE.to_string())?;
+    emit("index.html", render_index(&resolver))?;
+    for page in &pages {
+        emit(&page.html_name(), render_page(page, &resolver))?;
+    }
+    Ok(written)
+}
@@ -0,0 +1,580 @@
+//! 引用的生命周期（NLL）
+//!
+//! ownership.rs 讲引用时说：“一个引用的作用域从声明的地方开始一直持续到最后一次使用为止”，
+//! 所以 `r1`、`r2` 最后一次在 `println!` 里用完之后，再创建 `&mut s` 就没问题。这里对一个函数体
+//! 找出每个借用的创建位置和最后一次使用，画成按行排列的 ASCII 图，重叠的共享借用和可变借用标成冲突。
+//!
+//! 和 [`moves`](crate::moves) 一样只做简单的分析：`let r = &x;` 或者 `let w = f(&x);` 里的借用
+//! 归到 `r`、`w` 名下；没有绑定名字的 `&x` 和 `s.clear()` 这类修改自身的方法调用算作临时借用，
+//! 只活在那一行。分支和循环按代码顺序走一遍。
+
+use std::fmt;
+
+use proc_macro2::LineColumn;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprPath, ExprReference, Item, Pat};
+
+pub use crate::moves::Error;
+
+/// 会修改接收者、因而隐式获取 `&mut self` 的常见方法
+const MUTATING_METHODS: &[&str] = &[
+    "append",
+    "clear",
+    "dedup",
+    "drain",
+    "extend",
+    "insert",
+    "pop",
+    "push",
+    "push_str",
+    "remove",
+    "retain",
+    "reverse",
+    "sort",
+    "sort_by",
+    "sort_by_key",
+    "swap",
+    "truncate",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Shared,
+    Mutable,
+}
+
+/// 一次借用：从 `created` 行开始，活到 `last_use` 行。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loan {
+    /// 持有借用的变量名；临时借用是它的写法，例如 `&s`、`s.clear()`
+    pub name: String,
+    /// 被借用的变量
+    pub place: String,
+    pub kind: Kind,
+    pub created: usize,
+    pub last_use: usize,
+}
+
+impl Loan {
+    /// 借用的写法，例如 `&s`、`&mut s`
+    pub fn borrow(&self) -> String {
+        match self.kind {
+            Kind::Shared => format!("&{}", self.place),
+            Kind::Mutable => format!("&mut {}", self.place),
+        }
+    }
+
+    fn live_at(&self, line: usize) -> bool {
+        self.created <= line && line <= self.last_use
+    }
+}
+
+/// 违反借用规则的地方
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub line: usize,
+    /// 涉及的借用在 [`Chart::loans`] 里的下标
+    pub loans: Vec<usize>,
+    pub message: String,
+}
+
+/// 一个函数里所有借用的时间线
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub function: String,
+    pub loans: Vec<Loan>,
+    pub conflicts: Vec<Conflict>,
+    /// 函数体的起止行号
+    pub lines: (usize, usize),
+    source: Vec<String>,
+}
+
+/// 对被借用的变量直接读写
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone)]
+enum Var {
+    /// 普通变量，值是它在 `Collector::places` 里的下标；同名变量被隐藏后是另一个下标
+    Owner(usize),
+    /// 引用，值是它持有的借用
+    Ref(Vec<usize>),
+}
+
+struct Collector {
+    offset: usize,
+    places: Vec<String>,
+    scope: Vec<(String, Var)>,
+    loans: Vec<(usize, Loan)>,
+    accesses: Vec<(usize, Access, usize)>,
+    /// `let` 右边直接出现、借用要归到左边变量名下的 `&x`，按它在源码里的起始位置认
+    bind: Vec<LineColumn>,
+    binding: Option<String>,
+    bound: Vec<usize>,
+    /// 借用或者修改时已经处理过的变量路径的起始位置，不再算作一次读取
+    skip: Option<LineColumn>,
+}
+
+/// 表达式落在哪个变量上：`s`、`s.field`、`s[0..2]`、`(s)`。
+fn place_root(expr: &Expr) -> Option<&ExprPath> {
+    match expr {
+        Expr::Path(path) if path.path.get_ident().is_some() => Some(path),
+        Expr::Field(field) => place_root(&field.base),
+        Expr::Index(index) => place_root(&index.expr),
+        Expr::Paren(paren) => place_root(&paren.expr),
+        _ => None,
+    }
+}
+
+fn path_name(path: &ExprPath) -> String {
+    path.path
+        .get_ident()
+        .map(|i| i.to_string())
+        .unwrap_or_default()
+}
+
+fn pat_names(pat: &Pat, names: &mut Vec<String>) {
+    match pat {
+        Pat::Ident(pat_ident) => names.push(pat_ident.ident.to_string()),
+        Pat::Type(typed) => pat_names(&typed.pat, names),
+        Pat::Tuple(tuple) => tuple.elems.iter().for_each(|p| pat_names(p, names)),
+        Pat::Reference(reference) => pat_names(&reference.pat, names),
+        _ => {}
+    }
+}
+
+impl Collector {
+    fn line(&self, span: proc_macro2::Span) -> usize {
+        span.start().line.saturating_sub(self.offset)
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Var> {
+        self.scope
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, var)| var)
+    }
+
+    fn declare_owner(&mut self, name: String) {
+        self.places.push(name.clone());
+        self.scope.push((name, Var::Owner(self.places.len() - 1)));
+    }
+
+    fn use_name(&mut self, name: &str, line: usize) {
+        match self.lookup(name).cloned() {
+            Some(Var::Ref(loans)) => {
+                for loan in loans {
+                    let loan = &mut self.loans[loan].1;
+                    loan.last_use = loan.last_use.max(line);
+                }
+            }
+            Some(Var::Owner(place)) => self.accesses.push((place, Access::Read, line)),
+            None => {}
+        }
+    }
+
+    fn loan(&mut self, place: usize, name: String, kind: Kind, line: usize) -> usize {
+        self.loans.push((
+            place,
+            Loan {
+                name,
+                place: self.places[place].clone(),
+                kind,
+                created: line,
+                last_use: line,
+            },
+        ));
+        self.loans.len() - 1
+    }
+
+    /// 借用绑定到 `let` 左边的变量上时，记下来；否则就是临时借用。
+    fn held(&mut self, reference: &ExprReference) -> Option<String> {
+        if self.bind.contains(&reference.span().start()) {
+            self.binding.clone()
+        } else {
+            None
+        }
+    }
+
+    /// 修改变量本身：赋值、`+=`。
+    fn write(&mut self, left: &Expr, line: usize) {
+        match place_root(left) {
+            Some(root) => {
+                if let Some(Var::Owner(place)) = self.lookup(&path_name(root)).cloned() {
+                    self.accesses.push((place, Access::Write, line));
+                    self.skip = Some(root.span().start());
+                }
+                self.visit_expr(left);
+                self.skip = None;
+            }
+            None => self.visit_expr(left),
+        }
+    }
+
+    fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        for (j, (place_b, b)) in self.loans.iter().enumerate() {
+            for (i, (place_a, a)) in self.loans[..j].iter().enumerate() {
+                if place_a != place_b
+                    || (a.kind == Kind::Shared && b.kind == Kind::Shared)
+                    || a.created.max(b.created) > a.last_use.min(b.last_use)
+                {
+                    continue;
+                }
+                conflicts.push(Conflict {
+                    line: b.created,
+                    loans: vec![i, j],
+                    message: format!(
+                        "`{}` ({}) is created while `{}` ({}, line {}) is still used at line {}",
+                        b.name,
+                        b.borrow(),
+                        a.name,
+                        a.borrow(),
+                        a.created,
+                        a.last_use
+                    ),
+                });
+            }
+        }
+        for &(place, access, line) in &self.accesses {
+            for (i, (loan_place, loan)) in self.loans.iter().enumerate() {
+                if *loan_place != place
+                    || loan.created == line
+                    || !loan.live_at(line)
+                    || (access == Access::Read && loan.kind == Kind::Shared)
+                {
+                    continue;
+                }
+                let what = match access {
+                    Access::Read => "read",
+                    Access::Write => "assigned",
+                };
+                conflicts.push(Conflict {
+                    line,
+                    loans: vec![i],
+                    message: format!(
+                        "`{}` is {what} while `{}` ({}, line {}) is still used at line {}",
+                        loan.place,
+                        loan.name,
+                        loan.borrow(),
+                        loan.created,
+                        loan.last_use
+                    ),
+                });
+            }
+        }
+        conflicts.sort_by_key(|conflict| conflict.line);
+        conflicts
+    }
+}
+
+impl<'ast> Visit<'ast> for Collector {
+    fn visit_item(&mut self, _: &'ast Item) {}
+
+    fn visit_block(&mut self, block: &'ast syn::Block) {
+        let depth = self.scope.len();
+        visit::visit_block(self, block);
+        self.scope.truncate(depth);
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        let mut names = Vec::new();
+        pat_names(&local.pat, &mut names);
+        let mut held = None;
+        if let Some(init) = &local.init {
+            let saved = (
+                std::mem::take(&mut self.bind),
+                self.binding.take(),
+                std::mem::take(&mut self.bound),
+            );
+            let args: Vec<&Expr> = match &*init.expr {
+                Expr::Call(call) => call.args.iter().collect(),
+                Expr::MethodCall(call) => call.args.iter().collect(),
+                expr => vec![expr],
+            };
+            for arg in args {
+                if let Expr::Reference(reference) = arg {
+                    self.bind.push(reference.span().start());
+                }
+            }
+            if let [name] = names.as_slice() {
+                self.binding = Some(name.clone());
+            }
+            self.visit_expr(&init.expr);
+            if let Some((_, diverge)) = &init.diverge {
+                self.visit_expr(diverge);
+            }
+            // `let r2 = r1;` 把 r1 的借用交给 r2
+            if let Expr::Path(path) = &*init.expr {
+                if let Some(Var::Ref(loans)) = self.lookup(&path_name(path)) {
+                    self.bound.extend(loans.clone());
+                }
+            }
+            let bound = std::mem::replace(&mut self.bound, saved.2);
+            (self.bind, self.binding) = (saved.0, saved.1);
+            if !bound.is_empty() {
+                held = Some(bound);
+            }
+        }
+        match (names.as_slice(), held) {
+            ([name], Some(loans)) => self.scope.push((name.clone(), Var::Ref(loans))),
+            _ => names.into_iter().for_each(|name| self.declare_owner(name)),
+        }
+    }
+
+    fn visit_expr_reference(&mut self, reference: &'ast ExprReference) {
+        let line = self.line(reference.span());
+        let kind = match reference.mutability {
+            Some(_) => Kind::Mutable,
+            None => Kind::Shared,
+        };
+        let held = self.held(reference);
+        match place_root(&reference.expr) {
+            Some(root) => match self.lookup(&path_name(root)).cloned() {
+                Some(Var::Owner(place)) => {
+                    let loan = self.loan(place, held.clone().unwrap_or_default(), kind, line);
+                    if held.is_some() {
+                        self.bound.push(loan);
+                    } else {
+                        self.loans[loan].1.name = self.loans[loan].1.borrow();
+                    }
+                    self.skip = Some(root.span().start());
+                    self.visit_expr(&reference.expr);
+                    self.skip = None;
+                }
+                // 对引用再借用：新的引用也依赖原来的借用
+                Some(Var::Ref(loans)) => {
+                    if held.is_some() {
+                        self.bound.extend(loans);
+                    }
+                    self.visit_expr(&reference.expr);
+                }
+                None => self.visit_expr(&reference.expr),
+            },
+            None => self.visit_expr(&reference.expr),
+        }
+    }
+
+    fn visit_expr_path(&mut self, path: &'ast ExprPath) {
+        if self.skip == Some(path.span().start()) {
+            return;
+        }
+        if path.path.get_ident().is_some() {
+            let line = self.line(path.span());
+            self.use_name(&path_name(path), line);
+        }
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        let method = call.method.to_string();
+        let owner = match &*call.receiver {
+            Expr::Path(path) => match self.lookup(&path_name(path)) {
+                Some(Var::Owner(place)) => Some((*place, path)),
+                _ => None,
+            },
+            _ => None,
+        };
+        match owner {
+            Some((place, path)) if MUTATING_METHODS.contains(&method.as_str()) => {
+                let line = self.line(call.method.span());
+                let name = format!("{}.{method}()", self.places[place]);
+                self.loan(place, name, Kind::Mutable, line);
+                self.skip = Some(path.span().start());
+                self.visit_expr(&call.receiver);
+                self.skip = None;
+            }
+            _ => self.visit_expr(&call.receiver),
+        }
+        for arg in &call.args {
+            self.visit_expr(arg);
+        }
+    }
+
+    fn visit_expr_assign(&mut self, assign: &'ast syn::ExprAssign) {
+        self.visit_expr(&assign.right);
+        let line = self.line(assign.left.span());
+        self.write(&assign.left, line);
+    }
+
+    fn visit_expr_binary(&mut self, binary: &'ast syn::ExprBinary) {
+        use syn::BinOp::*;
+        match binary.op {
+            AddAssign(_) | SubAssign(_) | MulAssign(_) | DivAssign(_) | RemAssign(_)
+            | BitXorAssign(_) | BitAndAssign(_) | BitOrAssign(_) | ShlAssign(_) | ShrAssign(_) => {
+                self.visit_expr(&binary.right);
+                let line = self.line(binary.left.span());
+                self.write(&binary.left, line);
+            }
+            _ => visit::visit_expr_binary(self, binary),
+        }
+    }
+
+    /// `println!` 之类的宏：参数当作表达式，格式字符串里的 `{name}` 也算一次使用。
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        use syn::punctuated::Punctuated;
+        let Ok(args) = mac.parse_body_with(Punctuated::<Expr, syn::Token![,]>::parse_terminated)
+        else {
+            return;
+        };
+        for arg in &args {
+            if let Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(format),
+                ..
+            }) = arg
+            {
+                let line = self.line(format.span());
+                for name in crate::moves::inline_args(&format.value()) {
+                    self.use_name(&name, line);
+                }
+            } else {
+                self.visit_expr(arg);
+            }
+        }
+    }
+}
+
+fn chart(source: &str, parsed: &str, offset: usize, function: &str) -> Result<Chart, Error> {
+    let file = syn::parse_file(parsed)?;
+    let item = file
+        .items
+        .iter()
+        .find_map(|item| match item {
+            Item::Fn(f) if f.sig.ident == function => Some(f),
+            _ => None,
+        })
+        .ok_or_else(|| Error::NoFunction(function.to_string()))?;
+
+    let source_lines = source.lines().map(str::to_string).collect();
+    let braces = item.block.brace_token.span;
+    // 空函数体（包括空文件）没有可画的借用，只画 `{` 所在的一行；`analyze_body` 补上的
+    // `fn main() {` 算第 0 行，所以行号至少从 1 开始
+    if item.block.stmts.is_empty() {
+        let line = braces.open().start().line.saturating_sub(offset).max(1);
+        return Ok(Chart {
+            function: function.to_string(),
+            loans: Vec::new(),
+            conflicts: Vec::new(),
+            lines: (line, line),
+            source: source_lines,
+        });
+    }
+
+    let mut collector = Collector {
+        offset,
+        places: Vec::new(),
+        scope: Vec::new(),
+        loans: Vec::new(),
+        accesses: Vec::new(),
+        bind: Vec::new(),
+        binding: None,
+        bound: Vec::new(),
+        skip: None,
+    };
+    for input in &item.sig.inputs {
+        if let syn::FnArg::Typed(typed) = input {
+            let mut names = Vec::new();
+            pat_names(&typed.pat, &mut names);
+            names
+                .into_iter()
+                .for_each(|name| collector.declare_owner(name));
+        }
+    }
+    collector.visit_block(&item.block);
+
+    let (open, close) = (
+        collector.line(braces.open()),
+        collector.line(braces.close()),
+    );
+    let lines = if close > open + 1 {
+        (open + 1, close - 1)
+    } else {
+        (open.max(1), close.max(1))
+    };
+    Ok(Chart {
+        function: function.to_string(),
+        conflicts: collector.conflicts(),
+        loans: collector.loans.into_iter().map(|(_, loan)| loan).collect(),
+        lines,
+        source: source_lines,
+    })
+}
+
+/// 分析 `source` 里名为 `function` 的函数。
+pub fn analyze(source: &str, function: &str) -> Result<Chart, Error> {
+    chart(source, source, 0, function)
+}
+
+/// 分析一段没有包在函数里的语句，例如笔记注释里抄出来的片段。
+pub fn analyze_body(body: &str) -> Result<Chart, Error> {
+    // wrap_main 在前面加了一行 `fn main() {`
+    let offset = if body.contains("fn main") { 0 } else { 1 };
+    chart(body, &crate::snippet::wrap_main(body), offset, "main")
+}
+
+impl Chart {
+    /// 按行画出每个借用的存活区间：`&`/`M` 是创建共享/可变借用，`|` 是还活着，`x` 是最后一次使用，
+    /// 行尾的 `!` 表示这一行有冲突。
+    pub fn render(&self) -> String {
+        let widths: Vec<usize> = self
+            .loans
+            .iter()
+            .map(|loan| loan.name.chars().count() + 2)
+            .collect();
+        let mut out = format!("borrows in `{}`\n", self.function);
+        out.push_str("       ");
+        for (loan, width) in self.loans.iter().zip(&widths) {
+            out.push_str(&format!("{:<width$}", loan.name));
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+
+        for line in self.lines.0..=self.lines.1 {
+            let mut row = format!("{line:>4} | ");
+            for (loan, width) in self.loans.iter().zip(&widths) {
+                let cell = if line == loan.created {
+                    match loan.kind {
+                        Kind::Shared => '&',
+                        Kind::Mutable => 'M',
+                    }
+                } else if line == loan.last_use {
+                    'x'
+                } else if loan.live_at(line) {
+                    '|'
+                } else {
+                    ' '
+                };
+                row.push_str(&format!("{cell:<width$}"));
+            }
+            let flag = if self.conflicts.iter().any(|c| c.line == line) {
+                '!'
+            } else {
+                ' '
+            };
+            let text = self.source.get(line - 1).map_or("", |text| text.trim_end());
+            out.push_str(format!("{row}{flag} {text}").trim_end());
+            out.push('\n');
+        }
+
+        for conflict in &self.conflicts {
+            out.push_str(&format!(
+                "     ! line {}: {}\n",
+                conflict.line, conflict.message
+            ));
+        }
+        if self.conflicts.is_empty() {
+            out.push_str("     no conflicting borrows\n");
+        }
+        out
+    }
+}
+
+impl fmt::Display for Chart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
@@ -0,0 +1,59 @@
+//! 调用 rustc 检查一段代码能不能编译
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// 一次 rustc 调用的结果。
+#[derive(Debug)]
+pub struct Outcome {
+    pub success: bool,
+    pub stderr: String,
+}
+
+impl Outcome {
+    /// stderr 里出现的所有错误码，例如 `E0499`，按出现顺序去重。
+    pub fn error_codes(&self) -> Vec<&str> {
+        let mut codes = Vec::new();
+        for (at, _) in self.stderr.match_indices("error[E") {
+            let rest = &self.stderr[at + "error[".len()..];
+            if let Some(code) = rest.split(']').next() {
+                if !codes.contains(&code) {
+                    codes.push(code);
+                }
+            }
+        }
+        codes
+    }
+}
+
+/// 把 `code` 写到 `dir/name.rs` 并用 rustc 做一次检查（只生成 metadata，不生成可执行文件）。
+///
+/// 使用 `RUSTC` 环境变量指定的编译器，没有时用 `rustc`。
+pub fn check(name: &str, code: &str, dir: &Path) -> io::Result<Outcome> {
+    std::fs::create_dir_all(dir)?;
+    let file = dir.join(format!("{name}.rs"));
+    std::fs::write(&file, code)?;
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let output = Command::new(rustc)
+        .args([
+            "--edition",
+            "2021",
+            "--crate-type",
+            "bin",
+            "--emit=metadata",
+        ])
+        .args(["--error-format", "short", "-A", "warnings"])
+        .arg("--crate-name")
+        .arg(name)
+        .arg("--out-dir")
+        .arg(dir)
+        .arg(&file)
+        .output()?;
+
+    Ok(Outcome {
+        success: output.status.success(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
@@ -0,0 +1,77 @@
+//! 把示例的输出和仓库里保存的期望输出（golden 文件）对比
+
+use std::io;
+use std::path::Path;
+
+/// 逐行对比，返回统一格式的差异：` ` 开头的行两边相同，`-` 只在期望输出里，`+` 只在实际输出里。
+/// 两者相同时返回 `None`。
+pub fn diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    //最长公共子序列，lcs[i][j] 是 old[i..] 和 new[j..] 的长度
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let mut changed = false;
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            out.push_str(&format!(" {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push_str(&format!("-{}\n", old[i]));
+            changed = true;
+            i += 1;
+        } else {
+            out.push_str(&format!("+{}\n", new[j]));
+            changed = true;
+            j += 1;
+        }
+    }
+    if !changed {
+        //只有行尾的换行不同
+        out.push_str("(trailing newline differs)\n");
+    }
+    Some(out)
+}
+
+/// 对比 `path` 中的期望输出和 `actual`。`bless` 为真时直接用 `actual` 覆盖期望输出。
+///
+/// 不一致或 golden 文件不存在时返回说明和差异。
+pub fn check(path: &Path, actual: &str, bless: bool) -> io::Result<Result<(), String>> {
+    if bless {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, actual)?;
+        return Ok(Ok(()));
+    }
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(Err(format!("{} does not exist", path.display())));
+        }
+        Err(e) => return Err(e),
+    };
+    Ok(match diff(&expected, actual) {
+        None => Ok(()),
+        Some(diff) => Err(format!(
+            "{} differs (-expected +actual):\n{diff}",
+            path.display()
+        )),
+    })
+}
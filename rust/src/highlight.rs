@@ -0,0 +1,102 @@
+//! 简单的 Rust 语法高亮，输出带 class 的 HTML
+//!
+//! 只做词法上的区分：关键字、类型（大写开头的标识符）、宏、字符串、字符、数字、注释和生命周期，
+//! 够笔记里的示例用就行。
+
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "false", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self",
+    "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while",
+];
+
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64",
+    "u128", "usize", "f32", "f64",
+];
+
+/// 转义 HTML 中的特殊字符。
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn span(out: &mut String, class: &str, text: &str) {
+    out.push_str(&format!("<span class=\"{class}\">{}</span>", escape(text)));
+}
+
+/// 把一段 Rust 代码转成高亮后的 HTML（不含外层的 `<pre>`）。
+pub fn rust(code: &str) -> String {
+    let chars: Vec<(usize, char)> = code.char_indices().collect();
+    let at = |i: usize| chars.get(i).map_or('\0', |&(_, c)| c);
+    let offset = |i: usize| chars.get(i).map_or(code.len(), |&(o, _)| o);
+
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = at(i);
+        let start = i;
+        if c == '/' && at(i + 1) == '/' {
+            while i < chars.len() && at(i) != '\n' {
+                i += 1;
+            }
+            span(&mut out, "comment", &code[offset(start)..offset(i)]);
+        } else if c == '"' {
+            i += 1;
+            while i < chars.len() && at(i) != '"' {
+                i += if at(i) == '\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(chars.len());
+            span(&mut out, "string", &code[offset(start)..offset(i)]);
+        } else if c == '\'' {
+            //'a' 或 '\n' 是字符，'a 是生命周期
+            let len = if at(i + 1) == '\\' { 4 } else { 3 };
+            if at(i + len - 1) == '\'' {
+                i += len;
+                span(&mut out, "string", &code[offset(start)..offset(i)]);
+            } else {
+                i += 1;
+                while at(i).is_alphanumeric() || at(i) == '_' {
+                    i += 1;
+                }
+                span(&mut out, "lifetime", &code[offset(start)..offset(i)]);
+            }
+        } else if c.is_ascii_digit() {
+            while at(i).is_ascii_alphanumeric()
+                || at(i) == '_'
+                || (at(i) == '.' && at(i + 1).is_ascii_digit())
+            {
+                i += 1;
+            }
+            span(&mut out, "number", &code[offset(start)..offset(i)]);
+        } else if c.is_alphabetic() || c == '_' {
+            while at(i).is_alphanumeric() || at(i) == '_' {
+                i += 1;
+            }
+            let word = &code[offset(start)..offset(i)];
+            if at(i) == '!' && at(i + 1) != '=' {
+                i += 1;
+                span(&mut out, "macro", &code[offset(start)..offset(i)]);
+            } else if KEYWORDS.contains(&word) {
+                span(&mut out, "keyword", word);
+            } else if PRIMITIVES.contains(&word) || word.starts_with(char::is_uppercase) {
+                span(&mut out, "type", word);
+            } else {
+                out.push_str(&escape(word));
+            }
+        } else {
+            i += 1;
+            out.push_str(&escape(&code[offset(start)..offset(i)]));
+        }
+    }
+    out
+}
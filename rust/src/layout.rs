@@ -0,0 +1,300 @@
+//! 值在内存里的样子
+//!
+//! ownership.rs 说 `String` 在栈上是指针、长度、容量三部分，字符串内容在堆上；slice 是起始指针加长度；
+//! 字符串字面值直接存在二进制文件里。[`inspect`] 在运行时把这些都量出来：大小、对齐、栈上的表示，
+//! 指向的数据在哪儿、多长、容量多少，以及那块内存是静态区、堆还是栈。
+//!
+//! 判断内存区域要读 /proc/self/maps，只在 Linux 上可用，别的系统一律是 [`Region::Unknown`]。
+//! 零初始化的静态变量放在 .bss 里，它是匿名映射，会被当成堆。
+
+use std::fmt;
+use std::mem;
+
+/// 一块内存属于哪个区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// 可执行文件或者动态库映射进来的部分，例如字符串字面值
+    Static,
+    Heap,
+    Stack,
+    Unknown,
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Region::Static => "static",
+            Region::Heap => "heap",
+            Region::Stack => "stack",
+            Region::Unknown => "unknown",
+        })
+    }
+}
+
+/// 指针指向的数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pointee {
+    pub ptr: usize,
+    /// 元素个数；`str` 是字节数
+    pub len: usize,
+    /// 只有自己管理分配的类型（`String`、`Vec`）才有容量
+    pub cap: Option<usize>,
+    pub region: Region,
+}
+
+/// 一个值的布局
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// 去掉模块路径的类型名，例如 `(i32, String)`
+    pub type_name: String,
+    pub size: usize,
+    pub align: usize,
+    /// 值本身的地址
+    pub address: usize,
+    pub region: Region,
+    /// 栈上的表示：每个部分的名字和值，例如 `ptr`、`len`、`cap`
+    pub stack: Vec<(&'static str, String)>,
+    pub pointee: Option<Pointee>,
+    /// 元组的各个字段和它们相对值本身的偏移
+    pub fields: Vec<(usize, Layout)>,
+}
+
+/// 能被 [`inspect`] 测量的类型
+pub trait Inspect {
+    fn inspect(&self) -> Layout;
+}
+
+/// 测量 `value` 的布局。
+pub fn inspect<T: Inspect>(value: &T) -> Layout {
+    value.inspect()
+}
+
+fn base<T>(value: &T) -> Layout {
+    let address = value as *const T as usize;
+    Layout {
+        type_name: short_type_name(std::any::type_name::<T>()),
+        size: mem::size_of::<T>(),
+        align: mem::align_of::<T>(),
+        address,
+        region: region(address),
+        stack: Vec::new(),
+        pointee: None,
+        fields: Vec::new(),
+    }
+}
+
+fn pointee(ptr: usize, len: usize, cap: Option<usize>) -> Pointee {
+    Pointee {
+        ptr,
+        len,
+        cap,
+        region: region(ptr),
+    }
+}
+
+/// `alloc::vec::Vec<alloc::string::String>` → `Vec<String>`
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut segment = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            segment.clear();
+        } else if c.is_alphanumeric() || c == '_' {
+            segment.push(c);
+        } else {
+            short.push_str(&segment);
+            segment.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(&segment);
+    short
+}
+
+impl Inspect for String {
+    fn inspect(&self) -> Layout {
+        let mut layout = base(self);
+        let ptr = self.as_ptr() as usize;
+        layout.stack = vec![
+            ("ptr", format!("{ptr:#x}")),
+            ("len", self.len().to_string()),
+            ("cap", self.capacity().to_string()),
+        ];
+        // 容量为 0 时没有分配，指针只是个占位的悬垂值
+        if self.capacity() > 0 {
+            layout.pointee = Some(pointee(ptr, self.len(), Some(self.capacity())));
+        }
+        layout
+    }
+}
+
+impl<T> Inspect for Vec<T> {
+    fn inspect(&self) -> Layout {
+        let mut layout = base(self);
+        let ptr = self.as_ptr() as usize;
+        layout.stack = vec![
+            ("ptr", format!("{ptr:#x}")),
+            ("len", self.len().to_string()),
+            ("cap", self.capacity().to_string()),
+        ];
+        if self.capacity() > 0 && mem::size_of::<T>() > 0 {
+            layout.pointee = Some(pointee(ptr, self.len(), Some(self.capacity())));
+        }
+        layout
+    }
+}
+
+impl Inspect for &str {
+    fn inspect(&self) -> Layout {
+        let mut layout = base(self);
+        let ptr = self.as_ptr() as usize;
+        layout.stack = vec![
+            ("ptr", format!("{ptr:#x}")),
+            ("len", self.len().to_string()),
+        ];
+        layout.pointee = Some(pointee(ptr, self.len(), None));
+        layout
+    }
+}
+
+impl<T> Inspect for &[T] {
+    fn inspect(&self) -> Layout {
+        let mut layout = base(self);
+        let ptr = self.as_ptr() as usize;
+        layout.stack = vec![
+            ("ptr", format!("{ptr:#x}")),
+            ("len", self.len().to_string()),
+        ];
+        layout.pointee = Some(pointee(ptr, self.len(), None));
+        layout
+    }
+}
+
+/// 数组的元素就放在值本身里，没有指针。
+impl<T: fmt::Debug, const N: usize> Inspect for [T; N] {
+    fn inspect(&self) -> Layout {
+        let mut layout = base(self);
+        layout.stack = vec![("elements", format!("{self:?}"))];
+        layout
+    }
+}
+
+macro_rules! inspect_scalar {
+    ($($ty:ty),*) => {
+        $(impl Inspect for $ty {
+            fn inspect(&self) -> Layout {
+                let mut layout = base(self);
+                layout.stack = vec![("value", format!("{self:?}"))];
+                layout
+            }
+        })*
+    };
+}
+
+inspect_scalar!(bool, char, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+macro_rules! inspect_tuple {
+    ($($name:ident . $index:tt),+) => {
+        impl<$($name: Inspect),+> Inspect for ($($name,)+) {
+            fn inspect(&self) -> Layout {
+                let mut layout = base(self);
+                $(
+                    let field = self.$index.inspect();
+                    layout.fields.push((field.address - layout.address, field));
+                )+
+                layout.fields.sort_by_key(|(offset, _)| *offset);
+                layout
+            }
+        }
+    };
+}
+
+inspect_tuple!(A.0, B.1);
+inspect_tuple!(A.0, B.1, C.2);
+inspect_tuple!(A.0, B.1, C.2, D.3);
+
+/// `address` 落在哪个内存区域。
+#[cfg(target_os = "linux")]
+pub fn region(address: usize) -> Region {
+    let Ok(maps) = std::fs::read_to_string("/proc/self/maps") else {
+        return Region::Unknown;
+    };
+    // 当前线程的栈：子线程的栈在 maps 里是匿名映射，靠一个局部变量的地址认出来
+    let local = 0u8;
+    let here = &local as *const u8 as usize;
+    for line in maps.lines() {
+        // 地址范围 权限 偏移 设备 inode 路径
+        let mut parts = line.split_whitespace();
+        let Some((start, end)) = parts.next().and_then(|range| range.split_once('-')) else {
+            continue;
+        };
+        let (Ok(start), Ok(end)) = (
+            usize::from_str_radix(start, 16),
+            usize::from_str_radix(end, 16),
+        ) else {
+            continue;
+        };
+        if !(start..end).contains(&address) {
+            continue;
+        }
+        let path = parts.nth(4).unwrap_or("");
+        return match path {
+            "[stack]" => Region::Stack,
+            "[heap]" => Region::Heap,
+            _ if (start..end).contains(&here) => Region::Stack,
+            "" => Region::Heap,
+            _ if path.starts_with('[') => Region::Unknown,
+            _ => Region::Static,
+        };
+    }
+    Region::Unknown
+}
+
+/// `address` 落在哪个内存区域。
+#[cfg(not(target_os = "linux"))]
+pub fn region(_address: usize) -> Region {
+    Region::Unknown
+}
+
+impl Layout {
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        let pad = " ".repeat(indent);
+        writeln!(
+            f,
+            "{}  size {}  align {}  at {:#x} ({})",
+            self.type_name, self.size, self.align, self.address, self.region
+        )?;
+        if !self.stack.is_empty() {
+            let parts: Vec<String> = self
+                .stack
+                .iter()
+                .map(|(name, value)| format!("{name} = {value}"))
+                .collect();
+            writeln!(f, "{pad}  stack: {}", parts.join(", "))?;
+        }
+        if let Some(pointee) = &self.pointee {
+            write!(
+                f,
+                "{pad}  -> data at {:#x} ({}), len {}",
+                pointee.ptr, pointee.region, pointee.len
+            )?;
+            match pointee.cap {
+                Some(cap) => writeln!(f, ", cap {cap}")?,
+                None => writeln!(f)?,
+            }
+        }
+        for (offset, field) in &self.fields {
+            write!(f, "{pad}  +{offset:<3} ")?;
+            field.write(f, indent + 7)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
@@ -0,0 +1,75 @@
+//! 学习笔记的可运行部分
+//!
+//! 笔记本身是 rust/*.rs，里面的每个 `fn main` 小节都拆成了 examples/ 下的独立示例：
+//!
+//! | 示例                   | 笔记出处                 |
+//! |------------------------|--------------------------|
+//! | `shadowing`            | concepts.rs 变量与隐藏   |
+//! | `labeled_loops`        | concepts.rs 循环标签     |
+//! | `references`           | ownership.rs 拷贝与引用  |
+//! | `slices`               | ownership.rs Slice       |
+//! | `struct_definition`    | struct.rs 定义并实例化   |
+//! | `method_syntax`        | struct.rs 方法语法       |
+//! | `can_hold`             | struct.rs 带有更多参数的方法 |
+//! | `associated_functions` | struct.rs 关联函数       |
+//!
+//! cargo run --example <示例名>
+//!
+//! 笔记里那些“这段代码不能编译”的片段由 tests/compile_fail.rs 抽出来交给 rustc 验证。
+//! 注释里的其余代码片段由 `cargo run --bin extract_snippets` 抽到 snippets.md，作为
+//! [`note_snippets`] 的 doctest 运行。
+//!
+//! 每个示例的标准输出保存在 tests/golden/<示例名>.stdout，由 tests/golden.rs 对比；
+//! 有意修改了示例的输出之后，用 `BLESS=1 cargo test --test golden` 重新生成。
+//!
+//! `cargo run --bin book` 把笔记渲染成说明和代码左右对照的 HTML，见 [`book`]。
+//! `cargo run --bin annotations` 把作者的批注抽出来，生成复习报告和抽认卡，见 [`annotation`]。
+//! `cargo run --bin xref` 检查笔记里引用的章节和别的笔记是否都找得到，见 [`xref`]。
+//! `cargo run --bin moves -- <文件>` 逐条语句显示结构体字段的移动和拷贝，见 [`moves`]。
+//! `cargo run --bin borrows -- <文件>` 画出每个引用从创建到最后一次使用的区间，标出冲突的借用，见 [`borrows`]。
+//! `cargo run --bin layout` 在运行时量出 `String`、`&str`、slice、数组和元组的内存布局，见 [`layout`]。
+
+use std::path::{Path, PathBuf};
+
+pub mod annotation;
+pub mod book;
+pub mod borrows;
+pub mod compile;
+pub mod golden;
+pub mod highlight;
+pub mod layout;
+pub mod moves;
+pub mod snippet;
+pub mod xref;
+
+/// 笔记文件，按阅读顺序排列
+pub const NOTES: &[&str] = &[
+    "introduction.rs",
+    "concepts.rs",
+    "ownership.rs",
+    "struct.rs",
+];
+
+/// 笔记所在的目录
+pub fn notes_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf()
+}
+
+/// 按 [`NOTES`] 的顺序读出所有笔记，返回 (文件名, 内容)。
+pub fn read_notes() -> std::io::Result<Vec<(&'static str, String)>> {
+    let dir = notes_dir();
+    NOTES
+        .iter()
+        .map(|&file| Ok((file, std::fs::read_to_string(dir.join(file))?)))
+        .collect()
+}
+
+/// 读出 chapters.txt，书的章节表，见 [`xref::ChapterMap`]。
+pub fn read_chapter_map() -> std::io::Result<xref::ChapterMap> {
+    let text = std::fs::read_to_string(notes_dir().join("chapters.txt"))?;
+    xref::ChapterMap::parse(&text)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+#[doc = include_str!("../snippets.md")]
+pub mod note_snippets {}
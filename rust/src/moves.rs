@@ -0,0 +1,748 @@
+//! 结构体字段的移动与拷贝
+//!
+//! struct.rs 讲结构体更新语法时说：`let user2 = User { email: ..., ..user1 };` 把 `user1.username`
+//! 移动到了 `user2`，而 `active` 和 `sign_in_count` 是 Copy 的，只是拷贝。这里把一个函数逐条语句过一遍，
+//! 记下每个结构体变量的每个字段是否还能用，打印成一条时间线，部分移动就一目了然了。
+//!
+//! 只做简单的分析：结构体定义和函数返回类型从同一个文件里找；方法调用的接收者和宏参数都当作借用；
+//! 分支和循环按代码顺序走一遍，不区分走了哪条路。
+
+use std::fmt;
+
+use syn::spanned::Spanned;
+use syn::{Block, Expr, Fields, Item, Member, Pat, Stmt, Type};
+
+/// 分析失败的原因
+#[derive(Debug)]
+pub enum Error {
+    Parse(syn::Error),
+    NoFunction(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(e) => {
+                let start = e.span().start();
+                write!(f, "parse error at {}:{}: {e}", start.line, start.column + 1)
+            }
+            Error::NoFunction(name) => write!(f, "no function named `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<syn::Error> for Error {
+    fn from(e: syn::Error) -> Self {
+        Error::Parse(e)
+    }
+}
+
+/// 字段的状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldState {
+    Usable,
+    /// 在 `line` 行被移走，`to` 说明移到了哪里
+    Moved {
+        line: usize,
+        to: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub copy: bool,
+    pub state: FieldState,
+}
+
+/// 一个结构体类型的变量
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub name: String,
+    pub ty: String,
+    pub fields: Vec<Field>,
+}
+
+impl Binding {
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// 时间线上的一步：一条语句和执行完它之后各个变量的状态。
+#[derive(Debug, Clone)]
+pub struct Step {
+    /// 语句的起止行号
+    pub lines: (usize, usize),
+    pub bindings: Vec<Binding>,
+    /// 这条语句用到了已经被移走的值
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    pub function: String,
+    pub steps: Vec<Step>,
+    source: Vec<String>,
+}
+
+struct StructDef {
+    name: String,
+    copy: bool,
+    /// (字段名, 是否 Copy)
+    fields: Vec<(String, bool)>,
+}
+
+/// 从文件里找到的结构体定义和函数返回类型
+struct Model {
+    structs: Vec<StructDef>,
+    /// (函数名, 返回的结构体名)
+    returns: Vec<(String, String)>,
+}
+
+const COPY_TYPES: &[&str] = &[
+    "bool", "char", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128",
+    "usize", "f32", "f64",
+];
+
+fn derives_copy(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        let mut copy = false;
+        if attr.path().is_ident("derive") {
+            let _ = attr.parse_nested_meta(|meta| {
+                copy |= meta.path.is_ident("Copy");
+                Ok(())
+            });
+        }
+        copy
+    })
+}
+
+fn last_ident(path: &syn::Path) -> Option<String> {
+    path.segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+}
+
+/// 单个标识符组成的路径，例如 `user1`
+fn ident(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => path.path.get_ident().map(ToString::to_string),
+        Expr::Paren(paren) => ident(&paren.expr),
+        _ => None,
+    }
+}
+
+fn member(member: &Member) -> String {
+    match member {
+        Member::Named(ident) => ident.to_string(),
+        Member::Unnamed(index) => index.index.to_string(),
+    }
+}
+
+impl Model {
+    fn new(file: &syn::File) -> Self {
+        let copy_structs: Vec<String> = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Struct(s) if derives_copy(&s.attrs) => Some(s.ident.to_string()),
+                _ => None,
+            })
+            .collect();
+        let is_copy = |ty: &Type| type_is_copy(ty, &copy_structs);
+
+        let mut model = Model {
+            structs: Vec::new(),
+            returns: Vec::new(),
+        };
+        for item in &file.items {
+            match item {
+                Item::Struct(s) => {
+                    let fields = match &s.fields {
+                        Fields::Named(named) => named
+                            .named
+                            .iter()
+                            .map(|f| (f.ident.as_ref().unwrap().to_string(), is_copy(&f.ty)))
+                            .collect(),
+                        Fields::Unnamed(unnamed) => unnamed
+                            .unnamed
+                            .iter()
+                            .enumerate()
+                            .map(|(i, f)| (i.to_string(), is_copy(&f.ty)))
+                            .collect(),
+                        Fields::Unit => Vec::new(),
+                    };
+                    model.structs.push(StructDef {
+                        name: s.ident.to_string(),
+                        copy: copy_structs.contains(&s.ident.to_string()),
+                        fields,
+                    });
+                }
+                Item::Fn(f) => {
+                    if let syn::ReturnType::Type(_, ty) = &f.sig.output {
+                        if let Type::Path(path) = &**ty {
+                            if let Some(name) = last_ident(&path.path) {
+                                model.returns.push((f.sig.ident.to_string(), name));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        model
+    }
+
+    fn get(&self, name: &str) -> Option<&StructDef> {
+        self.structs.iter().find(|s| s.name == name)
+    }
+}
+
+fn type_is_copy(ty: &Type, copy_structs: &[String]) -> bool {
+    match ty {
+        Type::Path(path) => last_ident(&path.path).is_some_and(|name| {
+            COPY_TYPES.contains(&name.as_str()) || copy_structs.contains(&name)
+        }),
+        Type::Reference(reference) => reference.mutability.is_none(),
+        Type::Tuple(tuple) => tuple.elems.iter().all(|ty| type_is_copy(ty, copy_structs)),
+        Type::Array(array) => type_is_copy(&array.elem, copy_structs),
+        Type::Paren(paren) => type_is_copy(&paren.elem, copy_structs),
+        _ => false,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Move,
+    Borrow,
+}
+
+/// 一个可见的变量；`binding` 为 `None` 表示不是结构体，只是用来遮蔽外层的同名变量。
+struct Slot {
+    name: String,
+    binding: Option<Binding>,
+}
+
+struct Analyzer<'a> {
+    model: &'a Model,
+    slots: Vec<Slot>,
+    steps: Vec<Step>,
+    errors: Vec<String>,
+    /// 当前语句里被移走的值去了哪里
+    target: String,
+    line: usize,
+}
+
+impl Analyzer<'_> {
+    fn binding_mut(&mut self, name: &str) -> Option<&mut Binding> {
+        self.slots
+            .iter_mut()
+            .rev()
+            .find(|slot| slot.name == name)?
+            .binding
+            .as_mut()
+    }
+
+    fn use_field(&mut self, base: &str, field: &str, mode: Mode) {
+        let (line, target) = (self.line, self.target.clone());
+        let Some(binding) = self.binding_mut(base) else {
+            return;
+        };
+        let Some(f) = binding.fields.iter_mut().find(|f| f.name == field) else {
+            return;
+        };
+        if let FieldState::Moved { line: moved, .. } = f.state {
+            self.errors.push(format!(
+                "use of moved value `{base}.{field}` (moved at line {moved})"
+            ));
+            return;
+        }
+        if mode == Mode::Move && !f.copy {
+            f.state = FieldState::Moved { line, to: target };
+        }
+    }
+
+    fn use_whole(&mut self, name: &str, mode: Mode) {
+        let (line, target) = (self.line, self.target.clone());
+        let copy = |model: &Model, ty: &str| model.get(ty).is_some_and(|s| s.copy);
+        let model = self.model;
+        let Some(binding) = self.binding_mut(name) else {
+            return;
+        };
+        let moved: Vec<String> = binding
+            .fields
+            .iter()
+            .filter(|f| f.state != FieldState::Usable)
+            .map(|f| format!("`{}`", f.name))
+            .collect();
+        if !moved.is_empty() {
+            let error = if moved.len() == binding.fields.len() {
+                format!("use of moved value `{name}`")
+            } else {
+                format!(
+                    "use of partially moved value `{name}` ({} moved)",
+                    moved.join(", ")
+                )
+            };
+            self.errors.push(error);
+            return;
+        }
+        if mode == Mode::Move && !copy(model, &binding.ty) {
+            for f in &mut binding.fields {
+                f.state = FieldState::Moved {
+                    line,
+                    to: target.clone(),
+                };
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, mode: Mode) {
+        match expr {
+            Expr::Path(_) => {
+                if let Some(name) = ident(expr) {
+                    self.use_whole(&name, mode);
+                }
+            }
+            Expr::Field(field) => match ident(&field.base) {
+                Some(base) => self.use_field(&base, &member(&field.member), mode),
+                None => self.expr(&field.base, Mode::Borrow),
+            },
+            Expr::Reference(reference) => self.expr(&reference.expr, Mode::Borrow),
+            Expr::Struct(literal) => {
+                for field in &literal.fields {
+                    self.expr(&field.expr, Mode::Move);
+                }
+                if let Some(rest) = &literal.rest {
+                    self.struct_base(literal, rest);
+                }
+            }
+            Expr::Call(call) => {
+                let saved = std::mem::take(&mut self.target);
+                self.target = match &*call.func {
+                    Expr::Path(path) => {
+                        last_ident(&path.path).map_or(saved.clone(), |f| format!("{f}()"))
+                    }
+                    _ => saved.clone(),
+                };
+                for arg in &call.args {
+                    self.expr(arg, Mode::Move);
+                }
+                self.target = saved;
+            }
+            Expr::MethodCall(call) => {
+                self.expr(&call.receiver, Mode::Borrow);
+                for arg in &call.args {
+                    self.expr(arg, Mode::Move);
+                }
+            }
+            Expr::Macro(mac) => self.macro_args(&mac.mac),
+            Expr::Assign(assign) => {
+                self.expr(&assign.right, Mode::Move);
+                self.assign(&assign.left);
+            }
+            Expr::Binary(binary) => {
+                use syn::BinOp::*;
+                let left = match binary.op {
+                    Eq(_) | Ne(_) | Lt(_) | Le(_) | Gt(_) | Ge(_) => Mode::Borrow,
+                    _ => mode,
+                };
+                self.expr(&binary.left, left);
+                self.expr(&binary.right, left);
+            }
+            Expr::Unary(unary) => self.expr(&unary.expr, mode),
+            Expr::Paren(paren) => self.expr(&paren.expr, mode),
+            Expr::Group(group) => self.expr(&group.expr, mode),
+            Expr::Tuple(tuple) => tuple.elems.iter().for_each(|e| self.expr(e, mode)),
+            Expr::Array(array) => array.elems.iter().for_each(|e| self.expr(e, mode)),
+            Expr::Index(index) => {
+                self.expr(&index.expr, Mode::Borrow);
+                self.expr(&index.index, Mode::Move);
+            }
+            Expr::Cast(cast) => self.expr(&cast.expr, Mode::Move),
+            Expr::Return(ret) => ret.expr.iter().for_each(|e| self.expr(e, Mode::Move)),
+            Expr::Break(brk) => brk.expr.iter().for_each(|e| self.expr(e, Mode::Move)),
+            Expr::Block(block) => self.inline_block(&block.block),
+            Expr::Unsafe(block) => self.inline_block(&block.block),
+            Expr::If(expr_if) => {
+                self.expr(&expr_if.cond, Mode::Borrow);
+                self.inline_block(&expr_if.then_branch);
+                if let Some((_, otherwise)) = &expr_if.else_branch {
+                    self.expr(otherwise, mode);
+                }
+            }
+            Expr::Let(expr_let) => self.expr(&expr_let.expr, Mode::Borrow),
+            Expr::Match(expr_match) => {
+                self.expr(&expr_match.expr, Mode::Borrow);
+                for arm in &expr_match.arms {
+                    self.expr(&arm.body, mode);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// `..base`：没有显式给出的字段从 `base` 里取，非 Copy 的就被移走了。
+    fn struct_base(&mut self, literal: &syn::ExprStruct, rest: &Expr) {
+        let Some(base) = ident(rest) else {
+            self.expr(rest, Mode::Move);
+            return;
+        };
+        let given: Vec<String> = literal.fields.iter().map(|f| member(&f.member)).collect();
+        let remaining: Vec<String> = last_ident(&literal.path)
+            .and_then(|name| self.model.get(&name))
+            .map(|def| {
+                def.fields
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .filter(|name| !given.contains(name))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for field in remaining {
+            self.use_field(&base, &field, Mode::Move);
+        }
+    }
+
+    /// 赋值给字段或整个变量，被移走的值又可以用了。
+    fn assign(&mut self, left: &Expr) {
+        let (name, field) = match left {
+            Expr::Field(field) => (ident(&field.base), Some(member(&field.member))),
+            _ => (ident(left), None),
+        };
+        let Some(binding) = name.and_then(|name| self.binding_mut(&name)) else {
+            return;
+        };
+        for f in &mut binding.fields {
+            if field.as_ref().is_none_or(|field| &f.name == field) {
+                f.state = FieldState::Usable;
+            }
+        }
+    }
+
+    /// `println!` 之类的宏：参数当作表达式借用，格式字符串里的 `{name}` 也算借用。
+    fn macro_args(&mut self, mac: &syn::Macro) {
+        use syn::punctuated::Punctuated;
+        let Ok(args) = mac.parse_body_with(Punctuated::<Expr, syn::Token![,]>::parse_terminated)
+        else {
+            return;
+        };
+        for arg in &args {
+            if let Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(format),
+                ..
+            }) = arg
+            {
+                for name in inline_args(&format.value()) {
+                    self.use_whole(&name, Mode::Borrow);
+                }
+            } else {
+                self.expr(arg, Mode::Borrow);
+            }
+        }
+    }
+
+    /// 作为表达式一部分的块，例如 `let x = { ... };`，里面的语句不单独成步。
+    fn inline_block(&mut self, block: &Block) {
+        let depth = self.slots.len();
+        for stmt in &block.stmts {
+            self.stmt_effects(stmt);
+        }
+        self.slots.truncate(depth);
+    }
+
+    fn infer_type(&self, init: &Expr) -> Option<String> {
+        match init {
+            Expr::Struct(literal) => last_ident(&literal.path),
+            Expr::Call(call) => match &*call.func {
+                Expr::Path(path) => {
+                    let name = last_ident(&path.path)?;
+                    if self.model.get(&name).is_some() {
+                        //元组结构体的构造，例如 Color(0, 0, 0)
+                        return Some(name);
+                    }
+                    let (_, ty) = self.model.returns.iter().find(|(f, _)| *f == name)?;
+                    Some(ty.clone())
+                }
+                _ => None,
+            },
+            Expr::Path(path) => {
+                let name = path.path.get_ident()?.to_string();
+                if self.model.get(&name).is_some() {
+                    //类单元结构体，例如 AlwaysEqual
+                    return Some(name);
+                }
+                let slot = self.slots.iter().rev().find(|slot| slot.name == name)?;
+                Some(slot.binding.as_ref()?.ty.clone())
+            }
+            Expr::Paren(paren) => self.infer_type(&paren.expr),
+            _ => None,
+        }
+    }
+
+    fn declare(&mut self, name: String, ty: Option<String>) {
+        let binding = ty.and_then(|ty| self.model.get(&ty)).map(|def| Binding {
+            name: name.clone(),
+            ty: def.name.clone(),
+            fields: def
+                .fields
+                .iter()
+                .map(|(field, copy)| Field {
+                    name: field.clone(),
+                    copy: *copy,
+                    state: FieldState::Usable,
+                })
+                .collect(),
+        });
+        self.slots.push(Slot { name, binding });
+    }
+
+    fn local(&mut self, local: &syn::Local) {
+        let (pat, declared) = match &local.pat {
+            Pat::Type(typed) => (
+                &*typed.pat,
+                match &*typed.ty {
+                    Type::Path(path) => last_ident(&path.path),
+                    _ => None,
+                },
+            ),
+            pat => (pat, None),
+        };
+        let init = local.init.as_ref().map(|init| &*init.expr);
+
+        match pat {
+            Pat::Ident(pat_ident) => {
+                let name = pat_ident.ident.to_string();
+                if let Some(init) = init {
+                    self.target = name.clone();
+                    self.expr(init, Mode::Move);
+                }
+                let ty = declared.or_else(|| init.and_then(|init| self.infer_type(init)));
+                self.declare(name, ty);
+            }
+            //解构：按字段逐个移动，`_` 和 `..` 不移动
+            Pat::TupleStruct(_) | Pat::Struct(_) if init.and_then(ident).is_some() => {
+                let base = init.and_then(ident).unwrap();
+                let bound: Vec<(String, String)> = match pat {
+                    Pat::TupleStruct(tuple) => tuple
+                        .elems
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, p)| pat_name(p).map(|n| (i.to_string(), n)))
+                        .collect(),
+                    Pat::Struct(s) => s
+                        .fields
+                        .iter()
+                        .filter_map(|f| pat_name(&f.pat).map(|n| (member(&f.member), n)))
+                        .collect(),
+                    _ => unreachable!(),
+                };
+                for (field, name) in bound {
+                    self.target = name.clone();
+                    self.use_field(&base, &field, Mode::Move);
+                    self.declare(name, None);
+                }
+            }
+            _ => {
+                if let Some(init) = init {
+                    self.expr(init, Mode::Move);
+                }
+            }
+        }
+    }
+
+    /// 执行一条语句，但不记录为一步。
+    fn stmt_effects(&mut self, stmt: &Stmt) {
+        self.target = String::from("a temporary");
+        match stmt {
+            Stmt::Local(local) => self.local(local),
+            Stmt::Expr(expr, _) => self.expr(expr, Mode::Move),
+            Stmt::Macro(mac) => self.macro_args(&mac.mac),
+            Stmt::Item(_) => {}
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        let depth = self.slots.len();
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        self.slots.truncate(depth);
+    }
+
+    /// 执行一条语句并记录为一步；块、if、循环里的语句各自成步。
+    fn stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Expr(expr, _) = stmt {
+            let nested = match expr {
+                Expr::Block(b) => Some((None, &b.block)),
+                Expr::Unsafe(b) => Some((None, &b.block)),
+                Expr::Loop(l) => Some((None, &l.body)),
+                Expr::While(w) => Some((Some(&*w.cond), &w.body)),
+                Expr::ForLoop(f) => Some((Some(&*f.expr), &f.body)),
+                _ => None,
+            };
+            if let Some((head, body)) = nested {
+                if let Some(head) = head {
+                    self.record(head.span(), |a| a.expr(head, Mode::Borrow));
+                }
+                self.block(body);
+                return;
+            }
+            if let Expr::If(expr_if) = expr {
+                self.record(expr_if.cond.span(), |a| a.expr(&expr_if.cond, Mode::Borrow));
+                self.block(&expr_if.then_branch);
+                if let Some((_, otherwise)) = &expr_if.else_branch {
+                    match &**otherwise {
+                        Expr::Block(b) => self.block(&b.block),
+                        other => self.stmt(&Stmt::Expr(other.clone(), None)),
+                    }
+                }
+                return;
+            }
+        }
+        if let Stmt::Item(_) = stmt {
+            return;
+        }
+        self.record(stmt.span(), |a| a.stmt_effects(stmt));
+    }
+
+    fn record(&mut self, span: proc_macro2::Span, run: impl FnOnce(&mut Self)) {
+        let lines = (span.start().line, span.end().line);
+        self.line = lines.0;
+        run(self);
+        let bindings = self
+            .slots
+            .iter()
+            .filter_map(|slot| slot.binding.clone())
+            .filter(|binding| {
+                //被遮蔽的同名变量不显示
+                self.slots
+                    .iter()
+                    .rev()
+                    .find(|s| s.name == binding.name)
+                    .unwrap()
+                    .binding
+                    .as_ref()
+                    == Some(binding)
+            })
+            .collect();
+        self.steps.push(Step {
+            lines,
+            bindings,
+            errors: std::mem::take(&mut self.errors),
+        });
+    }
+}
+
+fn pat_name(pat: &Pat) -> Option<String> {
+    match pat {
+        Pat::Ident(pat_ident) => Some(pat_ident.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// 格式字符串里内联的参数名，例如 `"{x} and {y:?}"` 里的 `x` 和 `y`。
+pub(crate) fn inline_args(format: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = format;
+    while let Some(open) = rest.find('{') {
+        rest = &rest[open + 1..];
+        if rest.starts_with('{') {
+            rest = &rest[1..];
+            continue;
+        }
+        let end = rest.find(['}', ':']).unwrap_or(rest.len());
+        let name = &rest[..end];
+        if name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            names.push(name.to_string());
+        }
+        rest = &rest[end..];
+    }
+    names
+}
+
+/// 分析 `source` 里名为 `function` 的函数。
+pub fn analyze(source: &str, function: &str) -> Result<Timeline, Error> {
+    let file = syn::parse_file(source)?;
+    let model = Model::new(&file);
+    let body = file
+        .items
+        .iter()
+        .find_map(|item| match item {
+            Item::Fn(f) if f.sig.ident == function => Some(&f.block),
+            _ => None,
+        })
+        .ok_or_else(|| Error::NoFunction(function.to_string()))?;
+
+    let mut analyzer = Analyzer {
+        model: &model,
+        slots: Vec::new(),
+        steps: Vec::new(),
+        errors: Vec::new(),
+        target: String::new(),
+        line: 0,
+    };
+    analyzer.block(body);
+
+    Ok(Timeline {
+        function: function.to_string(),
+        steps: analyzer.steps,
+        source: source.lines().map(str::to_string).collect(),
+    })
+}
+
+impl Timeline {
+    /// 时间线的文字形式。
+    ///
+    /// `only` 给出时每一步都显示这个变量的状态；否则每一步只显示新声明或者状态有变化的变量。
+    pub fn render(&self, only: Option<&str>) -> String {
+        let mut out = format!("moves in `{}`\n", self.function);
+        let mut previous: &[Binding] = &[];
+        for step in &self.steps {
+            out.push('\n');
+            let (first, last) = step.lines;
+            for line in first..=last {
+                let text = self.source.get(line - 1).map_or("", |s| s.trim_end());
+                out.push_str(&format!("{line:>4} | {text}\n"));
+            }
+            for binding in &step.bindings {
+                let shown = match only {
+                    Some(only) => only == binding.name,
+                    None => !previous.contains(binding),
+                };
+                if !shown {
+                    continue;
+                }
+                let fields: Vec<String> = binding.fields.iter().map(field_label).collect();
+                let fields = if fields.is_empty() {
+                    String::from("(no fields)")
+                } else {
+                    fields.join(" | ")
+                };
+                out.push_str(&format!("     = {}: {fields}\n", binding.name));
+            }
+            previous = &step.bindings;
+            for error in &step.errors {
+                out.push_str(&format!("     ! error: {error}\n"));
+            }
+        }
+        out
+    }
+}
+
+fn field_label(field: &Field) -> String {
+    let copy = if field.copy { " (Copy)" } else { "" };
+    match &field.state {
+        FieldState::Usable => format!("{}{copy} ✓", field.name),
+        FieldState::Moved { line, to } => format!("{} ✗ moved to {to} at line {line}", field.name),
+    }
+}
+
+impl fmt::Display for Timeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(None))
+    }
+}
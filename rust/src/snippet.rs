@@ -0,0 +1,393 @@
+//! 从笔记里抠代码片段
+//!
+//! 笔记中的示例代码大多写在 `//` 注释里，并且比周围的说明文字缩进得更深：
+//!
+//! ```text
+//! //      fn main() {
+//! //          let reference_to_nothing = dangle();
+//! //      }
+//! ```
+
+/// 一行里 `//` 之后的内容，不是注释行时返回 `None`。
+pub(crate) fn comment_text(line: &str) -> Option<&str> {
+    line.trim_start().strip_prefix("//")
+}
+
+fn indent(text: &str) -> usize {
+    text.len() - text.trim_start().len()
+}
+
+/// 取出注释中从 `start` 行到 `end` 行（都包含）的一段代码，去掉 `//` 和公共缩进。
+///
+/// 两个锚点都按“去掉缩进后以它开头”来匹配。片段只在同一段缩进不浅于 `start` 的注释里找，
+/// 遇到非注释行或缩进更浅的说明文字就停下；同一段里有多个 `start` 时取离 `end` 最近的那个，
+/// 所以几个连在一起、开头相同的片段也能分别取出来。
+pub fn comment_block(source: &str, start: &str, end: &str) -> Option<String> {
+    let lines: Vec<Option<&str>> = source.lines().map(comment_text).collect();
+    let matches =
+        |i: usize, anchor: &str| lines[i].is_some_and(|text| text.trim_start().starts_with(anchor));
+
+    for first in (0..lines.len()).filter(|&i| matches(i, start)) {
+        let depth = indent(lines[first]?);
+        let region_end = (first + 1..lines.len())
+            .find(|&i| match lines[i] {
+                None => true,
+                Some(text) => !text.trim().is_empty() && indent(text) < depth,
+            })
+            .unwrap_or(lines.len());
+        let Some(last) = (first..region_end).rev().find(|&i| matches(i, end)) else {
+            continue;
+        };
+        let begin = (first..=last).rev().find(|&i| matches(i, start))?;
+        return Some(dedent(
+            lines[begin..=last].iter().map(|text| text.unwrap_or("")),
+        ));
+    }
+    None
+}
+
+/// 从第一处包含 `marker` 的行开始的那部分笔记，用来把 [`comment_block`] 的查找范围限定在某一节里。
+pub fn section<'a>(source: &'a str, marker: &str) -> Option<&'a str> {
+    let at = source.find(marker)?;
+    let line_start = source[..at].rfind('\n').map_or(0, |i| i + 1);
+    Some(&source[line_start..])
+}
+
+/// 取出笔记里真正的代码（不在注释里）中以 `start` 开头的条目，直到第一个顶格的 `}`。
+pub fn item(source: &str, start: &str) -> Option<String> {
+    let mut lines = source.lines().skip_while(|line| !line.starts_with(start));
+    let head = lines.next()?;
+    let mut code = String::from(head);
+    code.push('\n');
+    for line in lines {
+        code.push_str(line);
+        code.push('\n');
+        if line == "}" {
+            return Some(code);
+        }
+    }
+    None
+}
+
+/// 片段里没有 `fn main` 时，把它当作函数体包进 `fn main() { ... }`。
+pub fn wrap_main(code: &str) -> String {
+    if code.contains("fn main") {
+        return code.to_string();
+    }
+    let mut wrapped = String::from("fn main() {\n");
+    for line in code.lines() {
+        if !line.is_empty() {
+            wrapped.push_str("    ");
+            wrapped.push_str(line);
+        }
+        wrapped.push('\n');
+    }
+    wrapped.push_str("}\n");
+    wrapped
+}
+
+fn dedent<'a>(lines: impl Iterator<Item = &'a str> + Clone) -> String {
+    let common = lines
+        .clone()
+        .filter(|line| !line.trim().is_empty())
+        .map(indent)
+        .min()
+        .unwrap_or(0);
+    let mut code = String::new();
+    for line in lines {
+        code.push_str(line.get(common..).unwrap_or("").trim_end());
+        code.push('\n');
+    }
+    code
+}
+
+/// 片段应有的结果，由片段周围的说明文字决定，见 [`blocks`]。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expect {
+    /// 能编译并正常运行
+    Compile,
+    /// 不能编译，说明里给出了错误码时带上
+    Fail(Option<String>),
+    /// 能编译，运行时 panic
+    Panic,
+    /// 伪代码或不完整的代码，不检查
+    Ignore,
+}
+
+/// 笔记注释里的一段代码。
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// 片段第一行在笔记中的行号（从 1 开始）
+    pub line: usize,
+    pub code: String,
+    pub expect: Expect,
+}
+
+impl Block {
+    /// 片段最后一行在笔记中的行号
+    pub fn end_line(&self) -> usize {
+        self.line + self.code.lines().count() - 1
+    }
+}
+
+/// 片段前面的说明里表示“下面的代码不能编译”的说法
+const FAIL_MARKERS: &[&str] = &["不可以", "不能", "错误", "无效"];
+/// 片段后面的说明里表示“上面的代码不能编译”的说法；后面的说明常常是下一段的开头，所以只认这几个
+const FAIL_AFTER_MARKERS: &[&str] = &["抱怨", "error["];
+/// 片段自身注释里表示出错的说法，例如 `s.clear(); // 错误！`
+const FAIL_COMMENTS: &[&str] = &["错误", "大问题"];
+const PANIC_MARKERS: &[&str] = &["panic", "恐慌"];
+const IGNORE_MARKERS: &[&str] = &["伪代码", "签名"];
+/// 笔记里代替“随便什么代码”的占位符
+const PLACEHOLDERS: &[&str] = &["...", "xxx"];
+/// 笔记没有写出错误码的反例：片段里独有的一行和 rustc 给出的错误码。错误码只记在这里，
+/// tests/compile_fail.rs 从 [`blocks`] 的判断结果里取，再交给 rustc 核对。
+pub const ERROR_CODES: &[(&str, &str)] = &[
+    ("spaces = spaces.len();", "E0308"),
+    ("let r2 = &mut s;", "E0499"),
+    ("let r3 = &mut s;", "E0502"),
+    ("fn dangle() -> &String", "E0106"),
+    ("s.clear();", "E0502"),
+    ("username: &str,", "E0106"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    /// 不是注释
+    Source,
+    /// 空的注释行
+    Blank,
+    /// 说明文字、命令或命令输出
+    Prose,
+    Code,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{2E80}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' | '\u{FF00}'..='\u{FFEF}')
+}
+
+/// 去掉行尾 `//` 注释后的代码部分，字符串里的 `//` 不算。
+fn code_part(text: &str) -> &str {
+    let mut in_string = false;
+    let mut prev = '\0';
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' if prev != '\\' => in_string = !in_string,
+            '/' if prev == '/' && !in_string => return &text[..i - 1],
+            _ => {}
+        }
+        prev = c;
+    }
+    text
+}
+
+fn classify(source: &str) -> Vec<(Kind, &str)> {
+    let mut in_output = false;
+    source
+        .lines()
+        .map(|line| {
+            let Some(text) = comment_text(line) else {
+                in_output = false;
+                return (Kind::Source, line);
+            };
+            let trimmed = text.trim();
+            if trimmed.is_empty() {
+                in_output = false;
+                return (Kind::Blank, text);
+            }
+            //`$ cargo run` 之后直到空行都是命令输出
+            in_output |= trimmed.starts_with('$');
+            let command = ["cargo", "rustc", "rustup", "curl", "error", "warning", "= "]
+                .iter()
+                .any(|prefix| trimmed.starts_with(prefix))
+                || trimmed.ends_with(".rs");
+            //单独一个英文单词是小标题，例如 `//statements`
+            let heading = trimmed.chars().all(|c| c.is_ascii_alphabetic());
+            let kind = if in_output || command || heading || code_part(text).chars().any(is_cjk) {
+                Kind::Prose
+            } else {
+                Kind::Code
+            };
+            (kind, text)
+        })
+        .collect()
+}
+
+/// 紧挨着 `from` 的一段说明文字（中间可以隔着空行），`step` 为 -1 时往前找，1 时往后找。
+fn paragraph<'a>(lines: &[(Kind, &'a str)], from: usize, step: isize) -> Vec<&'a str> {
+    let mut texts = Vec::new();
+    let mut i = from as isize + step;
+    while let Some(&(kind, text)) = usize::try_from(i).ok().and_then(|i| lines.get(i)) {
+        match kind {
+            Kind::Blank if texts.is_empty() => {}
+            Kind::Prose => texts.push(text),
+            _ => break,
+        }
+        i += step;
+    }
+    texts
+}
+
+/// 用中文占位的语法模板，例如 `while 表达式 {  }`
+fn is_template(text: &str) -> bool {
+    let code = code_part(text).trim_end();
+    code.chars().any(is_cjk) && (code.ends_with('}') || code.ends_with(';'))
+}
+
+/// 能不能当作 Rust 解析：整个文件，或者 `fn main` 的函数体
+fn parses(code: &str) -> bool {
+    syn::parse_file(code).is_ok()
+        || syn::parse_str::<syn::Block>(&format!("{{\n{code}\n}}")).is_ok()
+}
+
+fn expect(code: &str, before: &[&str], after: &[&str]) -> Expect {
+    let mentions = |texts: &[&str], markers: &[&str]| {
+        texts
+            .iter()
+            .any(|text| markers.iter().any(|m| text.contains(m)))
+    };
+    if PLACEHOLDERS.iter().any(|p| code.contains(p))
+        || mentions(before, IGNORE_MARKERS)
+        || before.iter().chain(after).any(|text| is_template(text))
+        || !parses(code)
+    {
+        return Expect::Ignore;
+    }
+    if mentions(before, FAIL_MARKERS)
+        || mentions(after, FAIL_AFTER_MARKERS)
+        || mentions(&[code], FAIL_COMMENTS)
+    {
+        let error_code = after
+            .iter()
+            .find_map(|text| {
+                let rest = text.trim_start().strip_prefix("error[")?;
+                Some(rest.split(']').next()?.to_string())
+            })
+            .or_else(|| {
+                ERROR_CODES
+                    .iter()
+                    .find(|(line, _)| code.lines().any(|l| l.trim_start().starts_with(line)))
+                    .map(|(_, error_code)| error_code.to_string())
+            });
+        return Expect::Fail(error_code);
+    }
+    if mentions(before, PANIC_MARKERS) || mentions(after, PANIC_MARKERS) {
+        return Expect::Panic;
+    }
+    Expect::Compile
+}
+
+/// 找出笔记注释里所有的代码片段，并按周围的说明文字判断它们应有的结果。
+///
+/// 不含中文的注释行算作代码（行尾注释里的中文不算），`cargo ...`、`main.rs`、编译器报错和
+/// `$ cargo run` 之后的输出以及单独一个英文单词的小标题算作说明；连续的代码行（中间可以有空行、
+/// 缩进不浅于第一行）组成一段，没有以 `;`、`{`、`}` 结尾的行的不算代码。
+///
+/// 判断结果时看片段前后紧挨着的说明文字：
+/// - 片段里有 `...`、`xxx` 这类占位符，不能当作 Rust 解析，前面的说明提到“伪代码”“签名”，
+///   或者紧挨着 `while 表达式 {  }` 这样用中文占位的写法的，不检查；
+/// - 前面的说明里有“不可以”“不能”“错误”“无效”，后面的说明里有“抱怨”或 `error[Exxxx]`，
+///   或者片段自己的行尾注释写着“错误”“大问题”的，应该编译失败，错误码取自后面的 `error[Exxxx]`，
+///   笔记没写时查 [`ERROR_CODES`]；
+/// - 提到 panic 的应该在运行时 panic；
+/// - 其余的应该能编译运行。
+pub fn blocks(source: &str) -> Vec<Block> {
+    let lines = classify(source);
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].0 != Kind::Code {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let depth = indent(lines[start].1);
+        let mut end = start;
+        let mut j = start + 1;
+        while let Some(&(kind, text)) = lines.get(j) {
+            match kind {
+                Kind::Code if indent(text) >= depth => end = j,
+                Kind::Blank => {}
+                _ => break,
+            }
+            j += 1;
+        }
+        i = end + 1;
+
+        let code = dedent(lines[start..=end].iter().map(|&(_, text)| text));
+        let looks_like_rust = code
+            .lines()
+            .any(|line| line.ends_with(';') || line.ends_with('{') || line.ends_with('}'));
+        if !looks_like_rust {
+            continue;
+        }
+        let before = paragraph(&lines, start, -1);
+        let after = paragraph(&lines, end, 1);
+        blocks.push(Block {
+            line: start + 1,
+            expect: expect(&code, &before, &after),
+            code,
+        });
+    }
+    blocks
+}
+
+/// 片段调用了、自己却没有定义的笔记里的函数，拼在片段前面才能编译。
+pub fn prelude(source: &str, code: &str) -> String {
+    let mut prelude = String::new();
+    for line in source.lines() {
+        let Some(name) = line
+            .strip_prefix("fn ")
+            .and_then(|rest| rest.split('(').next())
+        else {
+            continue;
+        };
+        if !code.contains(&format!("{name}(")) || code.contains(&format!("fn {name}(")) {
+            continue;
+        }
+        if let Some(item) = item(source, line) {
+            prelude.push_str(&item);
+        }
+    }
+    prelude
+}
+
+/// 把各篇笔记里的片段写成一份 Markdown，每段放在一个带 rustdoc 属性的代码块里，
+/// 这样用 `#[doc = include_str!(...)]` 引进来之后，`cargo test` 会把它们当作 doctest 运行。
+///
+/// `notes` 是 (文件名, 内容) 的列表。
+pub fn doctests(notes: &[(&str, String)]) -> String {
+    let mut md = String::from(
+        "笔记注释里的代码片段，由 `cargo run --bin extract_snippets` 生成，不要手动修改。\n",
+    );
+    for (file, source) in notes {
+        let blocks = blocks(source);
+        if blocks.is_empty() {
+            continue;
+        }
+        md.push_str(&format!("\n# {file}\n"));
+        for block in blocks {
+            //`# ` 开头的行在 doctest 里参与编译但不显示
+            let prelude: String = prelude(source, &block.code)
+                .lines()
+                .map(|line| match line {
+                    "" => String::from("#\n"),
+                    line => format!("# {line}\n"),
+                })
+                .collect();
+            let attrs = match &block.expect {
+                Expect::Compile => String::from("rust"),
+                Expect::Fail(None) => String::from("compile_fail"),
+                Expect::Fail(Some(code)) => format!("compile_fail,{code}"),
+                Expect::Panic => String::from("should_panic"),
+                Expect::Ignore => String::from("text"),
+            };
+            md.push_str(&format!(
+                "\n{file}:{}\n\n```{attrs}\n{prelude}{}```\n",
+                block.line, block.code
+            ));
+        }
+    }
+    md
+}
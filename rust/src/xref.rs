@@ -0,0 +1,346 @@
+//! 笔记里的交叉引用
+//!
+//! 笔记会引用别的笔记（`./ownership.rs 移动部分`）和书里的章节（`第十章`、`第 7 章`、`附录 C`、
+//! `“元组类型”一节`）。前者解析到渲染后页面里的小标题，后者通过 chapters.txt 这张章节表解析。
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::book::Page;
+use crate::snippet;
+
+/// 一个引用指向的地方
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// 另一篇笔记，`section` 是“xx部分”里的 xx
+    Note {
+        file: String,
+        section: Option<String>,
+    },
+    /// 书的第几章
+    Chapter(u32),
+    /// 书的附录
+    Appendix(char),
+    /// 书里用引号括起来的一节
+    Section(String),
+}
+
+/// 文字中的一处引用，`range` 是它在文字中的字节范围。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub range: Range<usize>,
+    pub target: Target,
+}
+
+fn skip_spaces(text: &str, at: usize) -> usize {
+    at + (text[at..].len() - text[at..].trim_start_matches(' ').len())
+}
+
+/// 阿拉伯数字或“十五”这样的中文数字，返回值和结束位置。
+fn number(text: &str, at: usize) -> Option<(u32, usize)> {
+    let digits = text[at..].len()
+        - text[at..]
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    if digits > 0 {
+        return Some((text[at..at + digits].parse().ok()?, at + digits));
+    }
+
+    let mut end = at;
+    let (mut total, mut current) = (0, 0);
+    for c in text[at..].chars() {
+        let digit = "零一二三四五六七八九".chars().position(|d| d == c);
+        match (digit, c) {
+            (Some(d), _) => current = d as u32,
+            (None, '十') => {
+                total += current.max(1) * 10;
+                current = 0;
+            }
+            _ => break,
+        }
+        end += c.len_utf8();
+    }
+    (end > at).then_some((total + current, end))
+}
+
+/// `第 10 章`、`第十章`
+fn chapter(text: &str, at: usize) -> Option<(Target, usize)> {
+    let at = skip_spaces(text, at + "第".len());
+    let (n, end) = number(text, at)?;
+    let end = skip_spaces(text, end);
+    text[end..]
+        .starts_with('章')
+        .then(|| (Target::Chapter(n), end + "章".len()))
+}
+
+/// `附录 C`
+fn appendix(text: &str, at: usize) -> Option<(Target, usize)> {
+    let at = skip_spaces(text, at + "附录".len());
+    let letter = text[at..].chars().next().filter(char::is_ascii_uppercase)?;
+    Some((Target::Appendix(letter), at + 1))
+}
+
+/// `“元组类型”一节`、`“……”章节`、`“……”部分`
+fn section(text: &str, at: usize) -> Option<(Target, usize)> {
+    let open = at + "“".len();
+    let close = open + text[open..].find('”')?;
+    let title = &text[open..close];
+    let after = skip_spaces(text, close + "”".len());
+    let suffix = ["一节", "章节", "部分"]
+        .iter()
+        .find(|s| text[after..].starts_with(**s))?;
+    Some((Target::Section(title.to_string()), after + suffix.len()))
+}
+
+/// `./ownership.rs 移动部分`、`./ownership.rs`
+fn note(text: &str, at: usize) -> Option<(Target, usize)> {
+    let start = at + "./".len();
+    let name_len = text[start..].len()
+        - text[start..]
+            .trim_start_matches(|c: char| c.is_ascii_alphanumeric() || c == '_')
+            .len();
+    let end = start + name_len;
+    if name_len == 0 || !text[end..].starts_with(".rs") {
+        return None;
+    }
+    let file = text[start..end + ".rs".len()].to_string();
+    let end = end + ".rs".len();
+
+    //“xx部分”，xx 最多十个字，中间不能有标点和空格
+    let after = skip_spaces(text, end);
+    let name: String = text[after..]
+        .chars()
+        .take_while(|c| c.is_alphanumeric())
+        .take(12)
+        .collect();
+    let section = name
+        .find("部分")
+        .map(|at| &name[..at])
+        .filter(|s| !s.is_empty());
+    Some(match section {
+        Some(section) => (
+            Target::Note {
+                file,
+                section: Some(section.to_string()),
+            },
+            after + section.len() + "部分".len(),
+        ),
+        None => (
+            Target::Note {
+                file,
+                section: None,
+            },
+            end,
+        ),
+    })
+}
+
+/// 找出一段文字里的所有引用。
+pub fn find(text: &str) -> Vec<Reference> {
+    let mut refs = Vec::new();
+    let mut at = 0;
+    while at < text.len() {
+        let rest = &text[at..];
+        let found = if rest.starts_with('第') {
+            chapter(text, at)
+        } else if rest.starts_with("附录") {
+            appendix(text, at)
+        } else if rest.starts_with('“') {
+            section(text, at)
+        } else if rest.starts_with("./") {
+            note(text, at)
+        } else {
+            None
+        };
+        match found {
+            Some((target, end)) => {
+                refs.push(Reference {
+                    range: at..end,
+                    target,
+                });
+                at = end;
+            }
+            None => at += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    refs
+}
+
+/// 章节表中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub target: Target,
+    pub title: String,
+    pub url: String,
+}
+
+/// chapters.txt 的内容：每行 `引用 | 标题 | 链接`，`#` 开头的是注释。
+///
+/// 引用别的笔记、但那篇笔记里没有同名小标题的，写成 `./xx.rs xx部分 | 小标题`，解析到那个小标题。
+#[derive(Debug, Clone, Default)]
+pub struct ChapterMap {
+    entries: Vec<Entry>,
+}
+
+/// 章节表格式错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapError {
+    /// 行号，从 1 开始
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl ChapterMap {
+    pub fn parse(text: &str) -> Result<Self, MapError> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| MapError {
+                line: i + 1,
+                message,
+            };
+            let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+            let target = match &find(fields[0])[..] {
+                [reference] if reference.range == (0..fields[0].len()) => reference.target.clone(),
+                _ => {
+                    return Err(error(format!(
+                        "`{}` is not a chapter or section reference",
+                        fields[0]
+                    )))
+                }
+            };
+            let (title, url) = match (&target, &fields[1..]) {
+                (
+                    Target::Note {
+                        section: Some(_), ..
+                    },
+                    [heading],
+                ) => (heading, ""),
+                (Target::Note { .. }, _) => {
+                    return Err(error(format!(
+                        "expected `./note.rs section | heading`, got `{line}`"
+                    )))
+                }
+                (_, [title, url]) => (title, *url),
+                _ => {
+                    return Err(error(format!(
+                        "expected `reference | title | url`, got `{line}`"
+                    )))
+                }
+            };
+            entries.push(Entry {
+                target,
+                title: title.to_string(),
+                url: url.to_string(),
+            });
+        }
+        Ok(ChapterMap { entries })
+    }
+
+    pub fn get(&self, target: &Target) -> Option<&Entry> {
+        self.entries.iter().find(|entry| &entry.target == target)
+    }
+}
+
+/// 引用解析出来的链接
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub href: String,
+    pub title: String,
+}
+
+/// 用渲染后的页面和章节表解析引用。
+pub struct Resolver<'a> {
+    pages: &'a [Page],
+    chapters: &'a ChapterMap,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(pages: &'a [Page], chapters: &'a ChapterMap) -> Self {
+        Resolver { pages, chapters }
+    }
+
+    pub fn pages(&self) -> &'a [Page] {
+        self.pages
+    }
+
+    /// 引用指向的页面或章节，找不到时返回 `None`。
+    pub fn resolve(&self, target: &Target) -> Option<Link> {
+        match target {
+            Target::Note { file, section } => {
+                let page = self.pages.iter().find(|page| &page.file == file)?;
+                let Some(section) = section else {
+                    return Some(Link {
+                        href: page.html_name(),
+                        title: page.title.clone(),
+                    });
+                };
+                let section = self
+                    .chapters
+                    .get(target)
+                    .map_or(section, |alias| &alias.title);
+                let (anchor, heading) = page
+                    .toc()
+                    .into_iter()
+                    .find(|(_, heading)| heading.contains(section.as_str()))?;
+                Some(Link {
+                    href: format!("{}#{anchor}", page.html_name()),
+                    title: format!("{} · {heading}", page.title),
+                })
+            }
+            _ => self.chapters.get(target).map(|entry| Link {
+                href: entry.url.clone(),
+                title: entry.title.clone(),
+            }),
+        }
+    }
+}
+
+/// 解析不了的引用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dangling {
+    pub file: String,
+    /// 行号，从 1 开始
+    pub line: usize,
+    /// 引用的原文
+    pub text: String,
+}
+
+impl fmt::Display for Dangling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.text)
+    }
+}
+
+/// 检查所有笔记注释里的引用，返回解析不了的那些。
+pub fn check(notes: &[(&str, String)], resolver: &Resolver) -> Vec<Dangling> {
+    let mut dangling = Vec::new();
+    for (file, source) in notes {
+        for (i, line) in source.lines().enumerate() {
+            let Some(text) = snippet::comment_text(line) else {
+                continue;
+            };
+            for reference in find(text) {
+                if resolver.resolve(&reference.target).is_none() {
+                    dangling.push(Dangling {
+                        file: file.to_string(),
+                        line: i + 1,
+                        text: text[reference.range].to_string(),
+                    });
+                }
+            }
+        }
+    }
+    dangling
+}
@@ -3,6 +3,7 @@
 //但不同于元组，结构体需要命名各部分数据以便能清楚的表明其值的意义。
 //由于有了这些名字，结构体比元组更灵活：不需要依赖顺序来指定或访问实例中的值。
 //struct struct_name { field_name:field_type,... }
+//cargo run --example struct_definition
 
 struct User {
     active: bool,
@@ -312,6 +313,7 @@ fn build_user_field_init_shorthand(email: String, username: String) -> User {
 //方法语法
 //方法与函数类似：它们使用 fn 关键字和名称声明，可以拥有参数和返回值，同时包含在某处调用该方法时会执行的代码。
 //不过方法与函数是不同的，因为它们在结构体的上下文中被定义（或者是枚举或 trait 对象的上下文，将分别在第 6 章和第 17 章讲解），并且它们第一个参数总是 self，它代表调用该方法的结构体实例。
+//cargo run --example method_syntax
 
 #[derive(Debug)]
 struct Rectangle {
@@ -388,6 +390,7 @@ p1.distance(&p2);
   
   
 //带有更多参数的方法
+//cargo run --example can_hold
 //通过观察调用方法的代码可以看出参数是什么类型的：rect1.can_hold(&rect2) 传入了 &rect2，它是一个 Rectangle 的实例 rect2 的不可变借用。
 //这是可以理解的，因为我们只需要读取 rect2（而不是写入，这意味着我们需要一个不可变借用），而且希望 main 保持 rect2 的所有权，这样就可以在调用这个方法后继续使用它。
 impl Rectangle {
@@ -422,6 +425,7 @@ fn main() {
 //所有在 impl 块中定义的函数被称为关联函数（associated function），因为它们与 impl 后面命名的类型相关。
 //我们可以定义不以 self 为第一参数的关联函数（因此不是方法），因为它们并不作用于一个结构体的实例。
 //我们已经使用了一个这样的函数，String::from 函数，它是在 String 类型上定义的。
+//cargo run --example associated_functions
 
 impl Rectangle {
 //关联函数经常被用作返回一个结构体新实例的构造函数。
@@ -0,0 +1,51 @@
+//批注要连同出处和附近的代码一起抽出来。
+
+use rust_notes::annotation::{self, Annotation};
+use rust_notes::read_notes;
+
+#[test]
+fn extracts_subject_note_and_code() {
+    let source = "\
+fn main() {
+    //所有权规则    --      批注：有点像 cpp 的 unique_ptr
+    //1.Rust 中的每一个值都有一个 所有者（owner）。
+
+    let x = 5;
+    let y = x;
+
+    let s = String::new();
+}
+";
+    let annotations = annotation::extract("ownership.rs", source);
+    assert_eq!(
+        annotations,
+        vec![Annotation {
+            file: String::from("ownership.rs"),
+            line: 2,
+            subject: String::from("所有权规则"),
+            note: String::from("有点像 cpp 的 unique_ptr"),
+            code: Some((5, String::from("let x = 5;\nlet y = x;"))),
+        }]
+    );
+
+    let cards = annotation::flashcards(&annotations);
+    assert_eq!(
+        cards,
+        "所有权规则<br>（我的理解是？）\t有点像 cpp 的 unique_ptr<br>— ownership.rs:2\n"
+    );
+}
+
+#[test]
+fn every_annotation_in_the_notes_is_found() {
+    let notes = read_notes().unwrap();
+    let count: usize = notes
+        .iter()
+        .map(|(_, source)| source.matches("批注").count())
+        .sum();
+    let annotations: Vec<_> = notes
+        .iter()
+        .flat_map(|(file, source)| annotation::extract(file, source))
+        .collect();
+    assert_eq!(annotations.len(), count);
+    assert!(annotations.iter().all(|a| !a.note.is_empty()));
+}
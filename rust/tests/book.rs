@@ -0,0 +1,79 @@
+//笔记渲染成 HTML 时说明和代码要分开，小标题要进目录。
+
+use rust_notes::book::{self, Code};
+use rust_notes::xref::{ChapterMap, Resolver};
+use rust_notes::{highlight, read_chapter_map, read_notes};
+
+#[test]
+fn prose_and_code_are_separated() {
+    let source = "\
+//Slice
+//slice 是一种引用，所以它没有所有权。
+let s = String::from(\"hello\");
+let slice = &s[0..2];
+//也可以舍弃尾部的数字。
+let slice = &s[3..];
+";
+    let page = book::page("ownership.rs", source);
+    assert_eq!(page.toc(), vec![(String::from("s1"), "Slice")]);
+    assert_eq!(page.rows.len(), 2);
+    assert_eq!(
+        page.rows[0].prose,
+        vec!["slice 是一种引用，所以它没有所有权。"]
+    );
+    match &page.rows[0].code[..] {
+        [Code::Source { line: 3, text }] => assert!(text.ends_with("&s[0..2];")),
+        other => panic!("unexpected code {other:?}"),
+    }
+    assert_eq!(page.rows[1].prose, vec!["也可以舍弃尾部的数字。"]);
+}
+
+#[test]
+fn annotations_are_marked() {
+    let chapters = ChapterMap::default();
+    let resolver = Resolver::new(&[], &chapters);
+    let html = book::prose_html("所有权规则 -- 批注：有点像 <unique_ptr>", &resolver);
+    assert_eq!(
+        html,
+        "所有权规则 -- <span class=\"annotation\">批注：有点像 &lt;unique_ptr&gt;</span>"
+    );
+}
+
+#[test]
+fn annotated_headings_stay_headings() {
+    let source = "\
+//所有权规则    --      批注：有点想cpp的uniqur_ptr
+//1.Rust 中的每一个值都有一个 所有者（owner）。
+let s = String::from(\"hello\");
+";
+    let page = book::page("ownership.rs", source);
+    let toc = page.toc();
+    assert_eq!(toc.len(), 1);
+    assert!(toc[0].1.starts_with("所有权规则"), "{toc:?}");
+}
+
+#[test]
+fn highlight_distinguishes_tokens() {
+    let html = highlight::rust("let s: &'a str = \"hi\"; // c");
+    assert!(html.contains("<span class=\"keyword\">let</span>"));
+    assert!(html.contains("<span class=\"lifetime\">'a</span>"));
+    assert!(html.contains("<span class=\"type\">str</span>"));
+    assert!(html.contains("<span class=\"string\">&quot;hi&quot;</span>"));
+    assert!(html.contains("<span class=\"comment\">// c</span>"));
+}
+
+#[test]
+fn every_note_renders_with_a_toc() {
+    let notes = read_notes().unwrap();
+    let chapters = read_chapter_map().unwrap();
+    let pages: Vec<_> = notes
+        .iter()
+        .map(|(file, source)| book::page(file, source))
+        .collect();
+    let resolver = Resolver::new(&pages, &chapters);
+    for page in &pages {
+        assert!(!page.toc().is_empty(), "{} has no headings", page.file);
+        let html = book::render_page(page, &resolver);
+        assert!(html.contains("<ol class=\"toc\">"));
+    }
+}
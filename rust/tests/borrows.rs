@@ -0,0 +1,116 @@
+//引用活到最后一次使用为止：重叠的共享借用和可变借用是冲突，用完之后再借就没问题。
+
+use rust_notes::borrows::{self, Kind};
+
+#[test]
+fn mutable_borrow_while_shared_borrows_are_used_conflicts() {
+    let chart = borrows::analyze_body(
+        r#"let mut s = String::from("hello");
+let r1 = &s;
+let r2 = &s;
+let r3 = &mut s;
+println!("{}, {}, and {}", r1, r2, r3);
+"#,
+    )
+    .unwrap();
+    let spans: Vec<_> = chart
+        .loans
+        .iter()
+        .map(|loan| (loan.name.as_str(), loan.kind, loan.created, loan.last_use))
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            ("r1", Kind::Shared, 2, 5),
+            ("r2", Kind::Shared, 3, 5),
+            ("r3", Kind::Mutable, 4, 5),
+        ]
+    );
+    let lines: Vec<_> = chart
+        .conflicts
+        .iter()
+        .map(|c| (c.line, c.loans.clone()))
+        .collect();
+    assert_eq!(lines, [(4, vec![0, 2]), (4, vec![1, 2])]);
+}
+
+#[test]
+fn borrows_end_at_last_use() {
+    let chart = borrows::analyze_body(
+        r#"let mut s = String::from("hello");
+let r1 = &s;
+let r2 = &s;
+println!("{r1} and {r2}");
+let r3 = &mut s;
+println!("{r3}");
+let mut s = String::from("shadowed");
+let r4 = &mut s;
+r3.push_str("!");
+r4.push_str("?");
+"#,
+    )
+    .unwrap();
+    assert_eq!(chart.loans[0].last_use, 4);
+    assert_eq!(chart.loans[2].last_use, 9);
+    assert!(chart.conflicts.is_empty(), "{chart}");
+}
+
+#[test]
+fn mutating_method_conflicts_with_borrowed_result() {
+    let source = r#"
+fn first_word(s: &String) -> &str {
+    &s[..]
+}
+
+fn main() {
+    let mut s = String::from("hello world");
+    let word = first_word(&s);
+    s.clear();
+    println!("the first word is: {}", word);
+    s = String::new();
+}
+"#;
+    let chart = borrows::analyze(source, "main").unwrap();
+    assert_eq!(chart.lines, (7, 11));
+    assert_eq!(chart.loans[0].name, "word");
+    assert_eq!(chart.loans[1].name, "s.clear()");
+    assert_eq!(chart.conflicts.len(), 1);
+    assert_eq!(chart.conflicts[0].line, 9);
+
+    let rendered = chart.render();
+    assert!(
+        rendered.contains("   9 | |     M          !     s.clear();"),
+        "{rendered}"
+    );
+    assert!(rendered.contains("  10 | x"), "{rendered}");
+}
+
+#[test]
+fn reading_the_owner_while_mutably_borrowed_conflicts() {
+    let chart = borrows::analyze_body(
+        r#"let mut s = String::new();
+let r = &mut s;
+println!("{s}");
+r.push('x');
+"#,
+    )
+    .unwrap();
+    assert_eq!(chart.conflicts.len(), 1);
+    assert_eq!(chart.conflicts[0].line, 3);
+    assert!(chart.conflicts[0]
+        .message
+        .starts_with("`s` is read while `r` (&mut s"));
+}
+
+#[test]
+fn empty_bodies_chart_without_borrows() {
+    for chart in [
+        borrows::analyze_body("").unwrap(),
+        borrows::analyze_body("\n\n").unwrap(),
+        borrows::analyze("fn main() {}\n", "main").unwrap(),
+    ] {
+        assert!(chart.loans.is_empty());
+        assert_eq!(chart.lines, (1, 1));
+        assert!(chart.render().ends_with("no conflicting borrows\n"));
+    }
+}
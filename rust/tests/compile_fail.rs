@@ -0,0 +1,176 @@
+//笔记里说“不能编译”的片段，逐个交给 rustc，确认确实编译失败，并且错误码和笔记讲的一致。
+//错误码不在这里另写一份：笔记里写了 error[Exxxx] 的用笔记的，没写的查 snippet::ERROR_CODES。
+//换了工具链之后跑一遍 cargo test，就知道笔记里的说法还成不成立。
+
+use std::path::{Path, PathBuf};
+
+use rust_notes::snippet::Expect;
+use rust_notes::{compile, read_notes, snippet};
+
+/// 笔记里的一个反例。
+#[derive(Default)]
+struct Case {
+    name: &'static str,
+    file: &'static str,
+    /// 只在这一行之后找片段，为空时找整个文件。
+    section: &'static str,
+    start: &'static str,
+    end: &'static str,
+    /// 拼在片段前面的代码，例如片段里用到的笔记中的函数。
+    prelude: String,
+}
+
+fn note(file: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(file);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+}
+
+fn out_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("compile_fail")
+}
+
+/// 去掉缩进后的各行
+fn trimmed(code: &str) -> Vec<&str> {
+    code.lines().map(str::trim).collect()
+}
+
+/// 笔记对 `source` 里 `section` 之后包含 `block` 的片段给出的错误码
+fn expected_code(file: &str, source: &str, section: &str, block: &str) -> String {
+    let first_line = source[..source.len() - section.len()].lines().count() + 1;
+    let wanted = trimmed(block);
+    let found = snippet::blocks(source).into_iter().find(|b| {
+        b.line >= first_line
+            && trimmed(&b.code)
+                .windows(wanted.len())
+                .any(|lines| lines == wanted)
+    });
+    match found.map(|b| b.expect) {
+        Some(Expect::Fail(Some(code))) => code,
+        other => panic!("{file}: the notes give no error code for\n{block}\n(got {other:?})"),
+    }
+}
+
+fn assert_fails(case: Case) {
+    let Case {
+        name,
+        file,
+        section,
+        start,
+        end,
+        prelude,
+    } = case;
+    let full = note(file);
+    let source = snippet::section(&full, section)
+        .unwrap_or_else(|| panic!("{file}: no section `{section}`"));
+    let block = snippet::comment_block(source, start, end)
+        .unwrap_or_else(|| panic!("{file}: no snippet from `{start}` to `{end}`"));
+    let code = expected_code(file, &full, source, &block);
+    let program = format!("{prelude}{}", snippet::wrap_main(&block));
+
+    let outcome = compile::check(name, &program, &out_dir()).expect("failed to run rustc");
+    assert!(
+        !outcome.success,
+        "{file}: snippet `{name}` compiled, but the notes say it should not:\n{program}"
+    );
+    assert!(
+        outcome.error_codes().contains(&code.as_str()),
+        "{file}: snippet `{name}` expected error[{code}], got {:?}:\n{program}\n{}",
+        outcome.error_codes(),
+        outcome.stderr
+    );
+}
+
+#[test]
+fn shadowing_cannot_change_type_of_mut_variable() {
+    assert_fails(Case {
+        name: "mut_change_type",
+        file: "concepts.rs",
+        start: "let mut spaces",
+        end: "spaces = spaces.len();",
+        ..Case::default()
+    });
+}
+
+#[test]
+fn dangling_reference() {
+    assert_fails(Case {
+        name: "dangle",
+        file: "ownership.rs",
+        section: "让我们尝试创建一个悬垂引用",
+        start: "fn main() {",
+        end: "}   // 这里 s 离开作用域并被丢弃",
+        ..Case::default()
+    });
+}
+
+#[test]
+fn two_mutable_borrows() {
+    assert_fails(Case {
+        name: "two_mut_borrows",
+        file: "ownership.rs",
+        start: "let mut s = String::from(\"hello\");",
+        end: "println!(\"{}, {}\", r1, r2);",
+        ..Case::default()
+    });
+}
+
+#[test]
+fn mutable_borrow_while_shared() {
+    assert_fails(Case {
+        name: "mut_while_shared",
+        file: "ownership.rs",
+        start: "let mut s = String::from(\"hello\");",
+        end: "println!(\"{}, {}, and {}\", r1, r2, r3);",
+        ..Case::default()
+    });
+}
+
+#[test]
+fn clear_while_first_word_alive() {
+    assert_fails(Case {
+        name: "clear_while_borrowed",
+        file: "ownership.rs",
+        start: "let mut s = String::from(\"hello world\");",
+        end: "println!(\"the first word is: {word}\");",
+        prelude: snippet::item(&note("ownership.rs"), "fn first_word(").unwrap(),
+        ..Case::default()
+    });
+}
+
+#[test]
+fn struct_with_str_fields_needs_lifetimes() {
+    assert_fails(Case {
+        name: "user_without_lifetimes",
+        file: "struct.rs",
+        start: "struct User {",
+        end: "}",
+        ..Case::default()
+    });
+}
+
+#[test]
+fn struct_without_display() {
+    assert_fails(Case {
+        name: "rectangle_without_display",
+        file: "struct.rs",
+        section: "//通过派生 trait 增加实用功能",
+        start: "struct Rectangle {",
+        end: "}",
+        ..Case::default()
+    });
+}
+
+#[test]
+fn every_error_code_is_used() {
+    // 表里的每一行都要对应笔记里一个判定为编译失败的片段，免得片段改了之后表项没人用
+    let notes = read_notes().unwrap();
+    for (line, code) in snippet::ERROR_CODES {
+        let used = notes.iter().any(|(_, source)| {
+            snippet::blocks(source).into_iter().any(|block| {
+                block.code.lines().any(|l| l.trim_start().starts_with(line))
+                    && block.expect == Expect::Fail(Some(code.to_string()))
+            })
+        });
+        assert!(used, "no failing snippet uses `{line}` ({code})");
+    }
+}
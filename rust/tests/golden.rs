@@ -0,0 +1,55 @@
+//每个示例的标准输出都要和 tests/golden/<示例名>.stdout 一致，免得改示例时悄悄改变了笔记描述的行为。
+//
+//有意修改了输出之后重新生成：BLESS=1 cargo test --test golden
+
+use std::path::Path;
+use std::process::Command;
+
+use rust_notes::{golden, notes_dir};
+
+fn examples() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(notes_dir().join("examples"))
+        .unwrap()
+        .filter_map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_stem()?.to_str()?.to_string();
+            (path.extension()? == "rs").then_some(name)
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+fn run_example(name: &str) -> String {
+    let output = Command::new(env!("CARGO"))
+        .args(["run", "--quiet", "--example", name])
+        .current_dir(notes_dir())
+        .output()
+        .expect("failed to run cargo");
+    assert!(
+        output.status.success(),
+        "example `{name}` failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("example printed invalid UTF-8")
+}
+
+#[test]
+fn examples_match_golden_output() {
+    let bless = std::env::var_os("BLESS").is_some();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+
+    let mut failures = Vec::new();
+    for name in examples() {
+        let stdout = run_example(&name);
+        let path = dir.join(format!("{name}.stdout"));
+        if let Err(message) = golden::check(&path, &stdout, bless).unwrap() {
+            failures.push(message);
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\nrun `BLESS=1 cargo test --test golden` if the change is intended",
+        failures.join("\n")
+    );
+}
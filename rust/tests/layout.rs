@@ -0,0 +1,80 @@
+//ownership.rs 的内存模型：String 是栈上的指针/长度/容量加堆上的数据，字面值在静态区，slice 是指针加长度。
+
+use rust_notes::layout::{self, Region};
+
+#[test]
+fn string_is_pointer_length_capacity_to_heap() {
+    let mut s = String::with_capacity(16);
+    s.push_str("hello");
+    let layout = layout::inspect(&s);
+    assert_eq!(layout.type_name, "String");
+    assert_eq!(layout.size, 3 * std::mem::size_of::<usize>());
+    assert_eq!(layout.region, Region::Stack);
+    let names: Vec<_> = layout.stack.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["ptr", "len", "cap"]);
+
+    let data = layout.pointee.unwrap();
+    assert_eq!(data.ptr, s.as_ptr() as usize);
+    assert_eq!((data.len, data.cap), (5, Some(16)));
+    if cfg!(target_os = "linux") {
+        assert_eq!(data.region, Region::Heap);
+    }
+
+    assert_eq!(layout::inspect(&String::new()).pointee, None);
+}
+
+#[test]
+fn slices_point_into_their_source() {
+    let literal = "hello world";
+    let layout = layout::inspect(&literal);
+    assert_eq!(layout.type_name, "&str");
+    assert_eq!(layout.size, 2 * std::mem::size_of::<usize>());
+    let data = layout.pointee.unwrap();
+    assert_eq!((data.len, data.cap), (11, None));
+
+    let a = [1, 2, 3, 4, 5];
+    let slice = &a[1..3];
+    let array = layout::inspect(&a);
+    assert_eq!(
+        (array.type_name.as_str(), array.size, array.align),
+        ("[i32; 5]", 20, 4)
+    );
+    assert_eq!(array.pointee, None);
+    let data = layout::inspect(&slice).pointee.unwrap();
+    assert_eq!(data.ptr, array.address + 4);
+    assert_eq!(data.len, 2);
+
+    if cfg!(target_os = "linux") {
+        assert_eq!(
+            layout::inspect(&literal).pointee.unwrap().region,
+            Region::Static
+        );
+        assert_eq!(data.region, Region::Stack);
+    }
+}
+
+#[test]
+fn tuple_fields_are_laid_out_inline() {
+    let tuple = (500, String::from("tup"));
+    let layout = layout::inspect(&tuple);
+    assert_eq!(layout.type_name, "(i32, String)");
+    assert_eq!(layout.size, std::mem::size_of::<(i32, String)>());
+    let fields: Vec<_> = layout
+        .fields
+        .iter()
+        .map(|(offset, field)| (*offset, field.type_name.as_str()))
+        .collect();
+    assert_eq!(fields.len(), 2);
+    for (offset, field) in &layout.fields {
+        assert_eq!(field.address, layout.address + offset);
+        assert_eq!(field.region, layout.region);
+    }
+    let string = &layout
+        .fields
+        .iter()
+        .find(|(_, f)| f.type_name == "String")
+        .unwrap()
+        .1;
+    assert_eq!(string.pointee.as_ref().unwrap().len, 3);
+    assert!(layout.to_string().contains("String  size "));
+}
@@ -0,0 +1,75 @@
+//结构体更新语法只移走非 Copy 的字段；整个移走之后所有字段都不能用。
+
+use rust_notes::moves::{self, FieldState};
+
+const SOURCE: &str = r#"
+struct User {
+    active: bool,
+    username: String,
+    email: String,
+    sign_in_count: u64,
+}
+
+fn build_user(email: String, username: String) -> User {
+    User { email, username, active: true, sign_in_count: 1 }
+}
+
+fn main() {
+    let mut user1 = build_user(String::from("a@example.com"), String::from("a"));
+    let user2 = User {
+        email: String::from("another@example.com"),
+        ..user1
+    };
+    println!("{}", user1.active);
+    user1.username = String::from("again");
+    let user3 = user2;
+    println!("{}", user2.email);
+}
+"#;
+
+fn state(step: &moves::Step, binding: &str, field: &str) -> FieldState {
+    let binding = step.bindings.iter().find(|b| b.name == binding).unwrap();
+    binding.field(field).unwrap().state.clone()
+}
+
+#[test]
+fn struct_update_moves_only_non_copy_fields() {
+    let timeline = moves::analyze(SOURCE, "main").unwrap();
+    let steps = &timeline.steps;
+    assert_eq!(steps.len(), 6);
+
+    assert_eq!(steps[0].lines, (14, 14));
+    assert_eq!(state(&steps[0], "user1", "username"), FieldState::Usable);
+
+    let update = &steps[1];
+    assert_eq!(update.lines, (15, 18));
+    assert_eq!(
+        state(update, "user1", "username"),
+        FieldState::Moved {
+            line: 15,
+            to: String::from("user2")
+        }
+    );
+    assert_eq!(state(update, "user1", "email"), FieldState::Usable);
+    assert_eq!(state(update, "user1", "active"), FieldState::Usable);
+    assert!(steps[2].errors.is_empty());
+
+    //重新赋值之后字段又能用了
+    assert_eq!(state(&steps[3], "user1", "username"), FieldState::Usable);
+
+    assert!(matches!(
+        state(&steps[4], "user2", "active"),
+        FieldState::Moved { line: 21, .. }
+    ));
+    assert_eq!(
+        steps[5].errors,
+        vec!["use of moved value `user2.email` (moved at line 21)"]
+    );
+}
+
+#[test]
+fn reports_missing_function_and_parse_errors() {
+    let err = moves::analyze(SOURCE, "nope").unwrap_err();
+    assert_eq!(err.to_string(), "no function named `nope`");
+    assert!(moves::analyze("fn main() {", "main").is_err());
+}
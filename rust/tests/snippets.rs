@@ -0,0 +1,57 @@
+//snippets.md 是从笔记生成的，笔记改了却忘了重新生成时，这里会失败。
+
+use rust_notes::compile;
+use rust_notes::snippet::{self, Expect};
+use rust_notes::{notes_dir, read_notes};
+
+#[test]
+fn snippets_md_is_up_to_date() {
+    let generated = snippet::doctests(&read_notes().unwrap());
+    let current = std::fs::read_to_string(notes_dir().join("snippets.md")).unwrap_or_default();
+    assert!(
+        current == generated,
+        "snippets.md is out of date, run `cargo run --bin extract_snippets`"
+    );
+}
+
+#[test]
+fn every_failing_snippet_names_its_error_code() {
+    for (file, source) in read_notes().unwrap() {
+        for block in snippet::blocks(&source) {
+            assert_ne!(
+                block.expect,
+                Expect::Fail(None),
+                "{file}:{}: no error code for a snippet that should not compile",
+                block.line
+            );
+        }
+    }
+}
+
+//稳定版 rustdoc 不检查 compile_fail 后面的错误码，这里自己交给 rustc 核对
+#[test]
+fn failing_snippets_fail_with_their_error_code() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("snippets");
+    for (file, source) in read_notes().unwrap() {
+        for block in snippet::blocks(&source) {
+            let Expect::Fail(Some(code)) = &block.expect else {
+                continue;
+            };
+            let program = format!(
+                "{}{}",
+                snippet::prelude(&source, &block.code),
+                snippet::wrap_main(&block.code)
+            );
+            let name = format!("{}_{}", file.trim_end_matches(".rs"), block.line);
+            let outcome = compile::check(&name, &program, &dir).unwrap();
+            assert!(!outcome.success, "{file}:{} compiled", block.line);
+            assert_eq!(
+                outcome.error_codes(),
+                [code.as_str()],
+                "{file}:{}\n{program}\n{}",
+                block.line,
+                outcome.stderr
+            );
+        }
+    }
+}
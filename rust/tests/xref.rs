@@ -0,0 +1,134 @@
+//交叉引用的识别、章节表的解析，以及笔记里的引用都能解析到别的笔记的小标题或 chapters.txt 里的章节。
+
+use rust_notes::book;
+use rust_notes::xref::{self, ChapterMap, Resolver, Target};
+use rust_notes::{read_chapter_map, read_notes};
+
+fn targets(text: &str) -> Vec<Target> {
+    xref::find(text).into_iter().map(|r| r.target).collect()
+}
+
+#[test]
+fn finds_chapter_appendix_section_and_note_references() {
+    assert_eq!(
+        targets("第 6 章和第十七章讲解，见附录 C 中的说明"),
+        vec![
+            Target::Chapter(6),
+            Target::Chapter(17),
+            Target::Appendix('C')
+        ]
+    );
+    assert_eq!(targets("第十章"), vec![Target::Chapter(10)]);
+    assert_eq!(targets("第二十一章"), vec![Target::Chapter(21)]);
+    assert_eq!(
+        targets("在第 12 章 “将错误信息输出到标准错误” 一节中"),
+        vec![
+            Target::Chapter(12),
+            Target::Section(String::from("将错误信息输出到标准错误"))
+        ]
+    );
+    assert_eq!(targets("“深拷贝”"), vec![]);
+
+    let text = "就像我们在./ownership.rs 移动部分讲到的一样";
+    let refs = xref::find(text);
+    assert_eq!(
+        refs[0].target,
+        Target::Note {
+            file: String::from("ownership.rs"),
+            section: Some(String::from("移动"))
+        }
+    );
+    assert_eq!(&text[refs[0].range.clone()], "./ownership.rs 移动部分");
+}
+
+#[test]
+fn chapter_map_rejects_malformed_lines() {
+    let map = ChapterMap::parse("# 注释\n\n第 10 章 | 泛型 | https://example.com/ch10\n").unwrap();
+    assert_eq!(map.get(&Target::Chapter(10)).unwrap().title, "泛型");
+
+    let err = ChapterMap::parse("第 10 章 | 泛型\n").unwrap_err();
+    assert_eq!(err.line, 1);
+    let err = ChapterMap::parse("\n泛型 | 泛型 | https://example.com\n").unwrap_err();
+    assert_eq!(err.line, 2);
+    let err = ChapterMap::parse("./ownership.rs 移动部分 | 所有权 | https://example.com\n");
+    assert_eq!(err.unwrap_err().line, 1);
+}
+
+#[test]
+fn resolves_notes_to_headings() {
+    let notes = [
+        ("ownership.rs", String::from("//Slice\nlet a = 1;\n")),
+        (
+            "struct.rs",
+            String::from("//见 ./ownership.rs Slice部分 和 ./ownership.rs 移动部分\n"),
+        ),
+    ];
+    let pages: Vec<_> = notes
+        .iter()
+        .map(|(file, source)| book::page(file, source))
+        .collect();
+    let chapters = ChapterMap::default();
+    let resolver = Resolver::new(&pages, &chapters);
+
+    let link = resolver
+        .resolve(&Target::Note {
+            file: String::from("ownership.rs"),
+            section: Some(String::from("Slice")),
+        })
+        .unwrap();
+    assert_eq!(link.href, "ownership.html#s1");
+
+    let dangling = xref::check(&notes, &resolver);
+    assert_eq!(dangling.len(), 1);
+    assert_eq!(
+        dangling[0].to_string(),
+        "struct.rs:1: ./ownership.rs 移动部分"
+    );
+
+    let html = book::prose_html("见 ./ownership.rs Slice部分", &resolver);
+    assert!(html.contains("<a href=\"ownership.html#s1\""));
+
+    //章节表里的别名把没有同名小标题的小节指到别的小标题
+    let aliases = ChapterMap::parse("./ownership.rs 移动部分 | Slice\n").unwrap();
+    let resolver = Resolver::new(&pages, &aliases);
+    assert!(xref::check(&notes, &resolver).is_empty());
+}
+
+#[test]
+fn every_reference_in_the_notes_resolves() {
+    let notes = read_notes().unwrap();
+    let chapters = read_chapter_map().unwrap();
+    let pages: Vec<_> = notes
+        .iter()
+        .map(|(file, source)| book::page(file, source))
+        .collect();
+    let resolver = Resolver::new(&pages, &chapters);
+    let dangling: Vec<String> = xref::check(&notes, &resolver)
+        .into_iter()
+        .map(|d| d.to_string())
+        .collect();
+    assert!(dangling.is_empty(), "dangling references: {dangling:?}");
+}
+
+#[test]
+fn moves_and_clones_point_at_the_ownership_rules() {
+    let notes = read_notes().unwrap();
+    let chapters = read_chapter_map().unwrap();
+    let pages: Vec<_> = notes
+        .iter()
+        .map(|(file, source)| book::page(file, source))
+        .collect();
+    let resolver = Resolver::new(&pages, &chapters);
+    for section in ["移动", "克隆"] {
+        let target = Target::Note {
+            file: String::from("ownership.rs"),
+            section: Some(String::from(section)),
+        };
+        let link = resolver.resolve(&target).unwrap();
+        assert!(
+            link.title.contains("所有权规则"),
+            "{section}: {}",
+            link.title
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! 权限
+//!
+//! 基于角色的访问控制：[`Permission`] 是能做的具体操作，角色是一组权限，可以继承别的角色的全部权限。
+//! 角色和分配写在一个 TOML 策略文件里，文件里的用户按用户名写：
+//!
+//! ```toml
+//! [roles.viewer]
+//! permissions = ["read_users", "read_audit_log"]
+//!
+//! [roles.moderator]
+//! inherits = ["viewer"]
+//! permissions = ["suspend_users"]
+//!
+//! [assignments]
+//! alice = ["moderator"]
+//! ```
+//!
+//! [`Policy::load`] 读文件时就把继承展开，引用了不存在的角色或者继承成环都在这时报错，之后的检查只是查表。
+//! 读文件时还会在 [`Storage`] 里把用户名换成 [`UserId`]，分配是按编号记的：改名的账户保留角色，
+//! 删掉的账户的角色不会落到之后注册同名账户的人身上。
+//!
+//! 没有全局策略，要检查权限的地方拿到 `&Policy`，和 [`Clock`](crate::Clock)、[`Kdf`](crate::Kdf)
+//! 一样由调用方传进来：从存储里取出账户后调 [`User::can`]。不是 `Active` 状态的账户没有任何权限。
+//!
+//! ```
+//! use users::{build_user, MemoryStorage, Permission, Policy, Storage};
+//!
+//! let mut storage = MemoryStorage::new();
+//! let alice = build_user("alice@example.com".parse()?, "alice".parse()?);
+//! storage.insert(&alice)?;
+//! let policy = Policy::from_toml("[roles.viewer]\npermissions = [\"read_users\"]\n\
+//!                                 [assignments]\nalice = [\"viewer\"]\n", &storage)?;
+//!
+//! let (id, alice) = storage.find_by_username("alice")?.unwrap();
+//! assert!(alice.can(id, &policy, Permission::ReadUsers));
+//! assert!(!alice.can(id, &policy, Permission::DeleteUsers));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::registry::UserId;
+use crate::storage::{Storage, StorageError};
+use crate::user::User;
+
+/// 可以授予的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    ReadUsers,
+    CreateUsers,
+    UpdateUsers,
+    SuspendUsers,
+    DeleteUsers,
+    ReadAuditLog,
+    ManageSessions,
+    ManageRoles,
+}
+
+/// 策略文件里的一个角色
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Role {
+    #[serde(default)]
+    pub permissions: BTreeSet<Permission>,
+    /// 继承这些角色的所有权限
+    #[serde(default)]
+    pub inherits: Vec<String>,
+}
+
+/// 策略文件的内容
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    roles: BTreeMap<String, Role>,
+    #[serde(default)]
+    assignments: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// 策略有问题
+#[derive(Debug)]
+pub enum PolicyError {
+    Io(io::Error),
+    /// 不是合法的 TOML，或者有不认识的权限和字段
+    Parse(String),
+    /// `referenced_by` 引用了没有定义的角色 `role`
+    UnknownRole {
+        role: String,
+        referenced_by: String,
+    },
+    /// 角色继承成环，按继承顺序列出环上的角色
+    Cycle(Vec<String>),
+    /// 分配里的用户名在存储里找不到
+    UnknownUser(String),
+    /// 查用户名时存储出错
+    Storage(StorageError),
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::Io(e) => write!(f, "{e}"),
+            PolicyError::Parse(e) => write!(f, "invalid policy: {e}"),
+            PolicyError::UnknownRole {
+                role,
+                referenced_by,
+            } => write!(f, "unknown role `{role}` referenced by `{referenced_by}`"),
+            PolicyError::Cycle(roles) => {
+                write!(f, "role inheritance cycle: {}", roles.join(" -> "))
+            }
+            PolicyError::UnknownUser(username) => {
+                write!(f, "unknown user `{username}` in assignments")
+            }
+            PolicyError::Storage(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+impl From<io::Error> for PolicyError {
+    fn from(e: io::Error) -> Self {
+        PolicyError::Io(e)
+    }
+}
+
+impl From<StorageError> for PolicyError {
+    fn from(e: StorageError) -> Self {
+        PolicyError::Storage(e)
+    }
+}
+
+/// 角色、继承展开后的权限和用户的角色分配
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    /// 每个角色连同继承来的全部权限
+    roles: BTreeMap<String, BTreeSet<Permission>>,
+    /// 账户编号到角色
+    assignments: BTreeMap<UserId, BTreeSet<String>>,
+}
+
+/// 深度优先展开 `name` 的权限；`path` 是当前正在展开的继承链，用来发现环
+fn resolve(
+    name: &str,
+    roles: &BTreeMap<String, Role>,
+    resolved: &mut BTreeMap<String, BTreeSet<Permission>>,
+    path: &mut Vec<String>,
+) -> Result<BTreeSet<Permission>, PolicyError> {
+    if let Some(permissions) = resolved.get(name) {
+        return Ok(permissions.clone());
+    }
+    if let Some(start) = path.iter().position(|role| role == name) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(name.to_string());
+        return Err(PolicyError::Cycle(cycle));
+    }
+    let role = &roles[name];
+    path.push(name.to_string());
+    let mut permissions = role.permissions.clone();
+    for parent in &role.inherits {
+        if !roles.contains_key(parent) {
+            return Err(PolicyError::UnknownRole {
+                role: parent.clone(),
+                referenced_by: name.to_string(),
+            });
+        }
+        permissions.extend(resolve(parent, roles, resolved, path)?);
+    }
+    path.pop();
+    resolved.insert(name.to_string(), permissions.clone());
+    Ok(permissions)
+}
+
+impl Policy {
+    /// 由角色定义建策略，展开继承；还没有任何分配。
+    pub fn new(roles: BTreeMap<String, Role>) -> Result<Self, PolicyError> {
+        let mut resolved = BTreeMap::new();
+        for name in roles.keys() {
+            resolve(name, &roles, &mut resolved, &mut Vec::new())?;
+        }
+        Ok(Policy {
+            roles: resolved,
+            assignments: BTreeMap::new(),
+        })
+    }
+
+    /// 解析 TOML 格式的策略，分配里的用户名在 `storage` 里换成编号。
+    pub fn from_toml(text: &str, storage: &impl Storage) -> Result<Self, PolicyError> {
+        let file: PolicyFile =
+            toml::from_str(text).map_err(|e| PolicyError::Parse(e.to_string()))?;
+        let mut policy = Policy::new(file.roles)?;
+        for (username, roles) in file.assignments {
+            let (id, _) = storage
+                .find_by_username(&username)?
+                .ok_or_else(|| PolicyError::UnknownUser(username.clone()))?;
+            for role in roles {
+                if !policy.roles.contains_key(&role) {
+                    return Err(PolicyError::UnknownRole {
+                        role,
+                        referenced_by: username,
+                    });
+                }
+                policy.assign(id, &role)?;
+            }
+        }
+        Ok(policy)
+    }
+
+    /// 读策略文件。
+    pub fn load(path: &Path, storage: &impl Storage) -> Result<Self, PolicyError> {
+        Policy::from_toml(&fs::read_to_string(path)?, storage)
+    }
+
+    /// 角色的全部权限，包括继承来的
+    pub fn permissions(&self, role: &str) -> Option<&BTreeSet<Permission>> {
+        self.roles.get(role)
+    }
+
+    /// 分配给 `id` 的角色
+    pub fn roles_of(&self, id: UserId) -> impl Iterator<Item = &str> {
+        self.assignments
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// 给 `id` 分配一个已定义的角色。
+    pub fn assign(&mut self, id: UserId, role: &str) -> Result<(), PolicyError> {
+        if !self.roles.contains_key(role) {
+            return Err(PolicyError::UnknownRole {
+                role: role.to_string(),
+                referenced_by: id.to_string(),
+            });
+        }
+        self.assignments
+            .entry(id)
+            .or_default()
+            .insert(role.to_string());
+        Ok(())
+    }
+
+    /// 收回角色，`id` 本来没有这个角色时返回 `false`。
+    pub fn unassign(&mut self, id: UserId, role: &str) -> bool {
+        self.assignments
+            .get_mut(&id)
+            .is_some_and(|roles| roles.remove(role))
+    }
+
+    /// 编号为 `id` 的账户 `user` 是否有 `permission`：账户要是 `Active`，某个角色（含继承）
+    /// 带这个权限。账户从哪个存储取出来都行，见 [`User::can`]。
+    pub fn allows(&self, id: UserId, user: &User, permission: Permission) -> bool {
+        user.is_active()
+            && self.roles_of(id).any(|role| {
+                self.roles
+                    .get(role)
+                    .is_some_and(|permissions| permissions.contains(&permission))
+            })
+    }
+}
+
+impl User {
+    /// 按 `policy` 检查这个账户能不能做 `permission`；`id` 是它在存储里的编号，
+    /// 和 [`Storage::get`]、[`Storage::find_by_username`] 取出账户时一起拿到。
+    pub fn can(&self, id: UserId, policy: &Policy, permission: Permission) -> bool {
+        policy.allows(id, self, permission)
+    }
+}
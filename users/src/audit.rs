@@ -0,0 +1,432 @@
+//! 审计日志
+//!
+//! struct.rs 里的 `user1.email = String::from("anotheremail@example.com")` 改完就什么都没留下。
+//! [`AuditLog`] 把每次修改记成一条只追加的 [`Event`]：改了谁、改了什么、什么时候、谁改的。
+//! 事件本身就是事实的来源——从头重放所有事件可以重建出每个账户的当前状态，见 [`AuditLog::replay`]。
+//!
+//! 注册表的每一种修改都有对应的方法，改注册表的同时记事件，修改被拒绝时不记：
+//! [`AuditLog::create`]、[`AuditLog::change_email`]、[`AuditLog::change_status`]，任意修改和
+//! [`UserPatch`] 走 [`AuditLog::update`] 和 [`AuditLog::apply_patch`]，登录走 [`AuditLog::sign_in`]，
+//! 成功、失败和顺手重算的密码哈希都会记下来。
+//!
+//! 密码哈希不进日志：只追加的日志删不掉，哈希一旦写进去就永远留着。事件里的用户 `password`
+//! 总是 `None`，改密码只记一条 [`Change::PasswordChanged`]，所以重放出来的账户没有密码，
+//! 其余字段都和注册表一致。
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::Clock;
+use crate::email::Email;
+use crate::patch::UserPatch;
+use crate::registry::{RegistryError, UserId, UserRegistry};
+use crate::signin::{Authenticator, SignInError};
+use crate::status::{Status, TransitionError};
+use crate::user::User;
+
+/// 谁做的修改
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Actor {
+    /// 后台任务、迁移等没有具体操作人的修改
+    System,
+    User(UserId),
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Actor::System => write!(f, "system"),
+            Actor::User(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+/// 发生了什么
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Change {
+    /// 账户创建时的完整状态，不含密码哈希
+    Created(User),
+    EmailChanged {
+        from: Email,
+        to: Email,
+    },
+    /// 账户状态按 [`Status`] 的规则转移
+    StatusChanged {
+        from: Status,
+        to: Status,
+    },
+    /// 其余字段的修改，不含密码
+    Updated(UserPatch),
+    /// 设置或者更换了密码，新的哈希不记
+    PasswordChanged,
+    SignedIn,
+    /// 登录成功时凭据按新参数重算了
+    PasswordRehashed,
+    /// 登录失败后的连续失败次数和锁定时间
+    SignInFailed {
+        failed_sign_ins: u32,
+        #[serde(default, with = "crate::clock::unix_seconds")]
+        locked_until: Option<SystemTime>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Created(user) => write!(f, "created `{}`", user.username),
+            Change::EmailChanged { from, to } => write!(f, "email changed from {from} to {to}"),
+            Change::StatusChanged { from, to } => write!(f, "status changed from {from} to {to}"),
+            Change::Updated(patch) => write!(f, "updated {}", patch.fields().join(", ")),
+            Change::PasswordChanged => write!(f, "password changed"),
+            Change::SignedIn => write!(f, "signed in"),
+            Change::PasswordRehashed => write!(f, "password rehashed"),
+            Change::SignInFailed {
+                failed_sign_ins, ..
+            } => write!(f, "sign-in failed ({failed_sign_ins} in a row)"),
+        }
+    }
+}
+
+/// 日志里的一条记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    /// 在日志里的序号，从 0 开始连续递增
+    pub seq: u64,
+    pub user: UserId,
+    pub at: SystemTime,
+    pub actor: Actor,
+    pub change: Change,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self
+            .at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        write!(
+            f,
+            "{} [{secs}] {} by {}: {}",
+            self.seq, self.user, self.actor, self.change
+        )
+    }
+}
+
+/// 重放失败：事件序列和账户的生命周期对不上
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// 账户还没创建就有了别的事件
+    NotCreated { seq: u64, user: UserId },
+    /// 同一个账户创建了两次
+    AlreadyCreated { seq: u64, user: UserId },
+    /// 状态转移不合规则
+    Transition {
+        seq: u64,
+        user: UserId,
+        error: TransitionError,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::NotCreated { seq, user } => {
+                write!(f, "event {seq}: user {user} has not been created")
+            }
+            ReplayError::AlreadyCreated { seq, user } => {
+                write!(f, "event {seq}: user {user} was already created")
+            }
+            ReplayError::Transition { seq, user, error } => {
+                write!(f, "event {seq}: user {user}: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// [`AuditLog::change_status`] 和 [`AuditLog::update`] 失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditError {
+    Registry(RegistryError),
+    Transition(TransitionError),
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditError::Registry(e) => write!(f, "{e}"),
+            AuditError::Transition(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+impl From<RegistryError> for AuditError {
+    fn from(e: RegistryError) -> Self {
+        AuditError::Registry(e)
+    }
+}
+
+impl From<TransitionError> for AuditError {
+    fn from(e: TransitionError) -> Self {
+        AuditError::Transition(e)
+    }
+}
+
+/// 把一条事件作用到账户的状态上；`None` 表示还没创建
+fn apply(state: &mut Option<User>, event: &Event) -> Result<(), ReplayError> {
+    let (seq, user) = (event.seq, event.user);
+    match (&event.change, state.as_mut()) {
+        (Change::Created(created), None) => *state = Some(created.clone()),
+        (Change::Created(_), Some(_)) => return Err(ReplayError::AlreadyCreated { seq, user }),
+        (_, None) => return Err(ReplayError::NotCreated { seq, user }),
+        (Change::EmailChanged { to, .. }, Some(current)) => current.email = to.clone(),
+        (Change::StatusChanged { to, .. }, Some(current)) => current
+            .transition(to.clone(), event.at)
+            .map_err(|error| ReplayError::Transition { seq, user, error })?,
+        (Change::Updated(patch), Some(current)) => patch.apply(current),
+        (Change::PasswordChanged | Change::PasswordRehashed, Some(_)) => {}
+        (Change::SignedIn, Some(current)) => {
+            current.sign_in_count += 1;
+            current.last_sign_in = Some(event.at);
+            current.failed_sign_ins = 0;
+            current.locked_until = None;
+        }
+        (
+            Change::SignInFailed {
+                failed_sign_ins,
+                locked_until,
+            },
+            Some(current),
+        ) => {
+            current.failed_sign_ins = *failed_sign_ins;
+            current.locked_until = *locked_until;
+        }
+    }
+    Ok(())
+}
+
+/// 只追加的事件日志，时间从注入的 [`Clock`] 取
+#[derive(Debug)]
+pub struct AuditLog<C> {
+    events: Vec<Event>,
+    clock: C,
+}
+
+impl<C: Clock> AuditLog<C> {
+    pub fn new(clock: C) -> Self {
+        AuditLog {
+            events: Vec::new(),
+            clock,
+        }
+    }
+
+    /// 接着已有的事件继续记，例如从文件读回来的日志
+    pub fn from_events(events: Vec<Event>, clock: C) -> Self {
+        AuditLog { events, clock }
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// 追加一条事件。
+    pub fn record(&mut self, user: UserId, actor: Actor, change: Change) -> &Event {
+        let at = self.clock.now();
+        self.record_at(user, actor, change, at)
+    }
+
+    /// 追加一条发生在 `at` 的事件；修改本身用了哪个时间，事件就记哪个，重放才对得上。
+    fn record_at(&mut self, user: UserId, actor: Actor, change: Change, at: SystemTime) -> &Event {
+        let event = Event {
+            seq: self.events.len() as u64,
+            user,
+            at,
+            actor,
+            change,
+        };
+        self.events.push(event);
+        self.events.last().expect("just pushed")
+    }
+
+    /// 在注册表里创建账户并记一条 `Created`，带着密码创建时再记一条 `PasswordChanged`。
+    pub fn create(
+        &mut self,
+        registry: &mut UserRegistry,
+        actor: Actor,
+        user: User,
+    ) -> Result<UserId, RegistryError> {
+        let mut logged = user.clone();
+        let password = logged.password.take();
+        let id = registry.create(user)?;
+        self.record(id, actor, Change::Created(logged));
+        if password.is_some() {
+            self.record(id, actor, Change::PasswordChanged);
+        }
+        Ok(id)
+    }
+
+    /// 用 `change` 修改账户，和 [`UserRegistry::update`] 一样；改了的字段记成一条 `Updated`，
+    /// 密码变了另记一条 `PasswordChanged`。状态变了按 [`User::transition`] 在当前时间转移，
+    /// 不允许的转移返回错误，账户不变；允许的记一条 `StatusChanged`。什么都没改时不记。
+    pub fn update(
+        &mut self,
+        registry: &mut UserRegistry,
+        id: UserId,
+        actor: Actor,
+        change: impl FnOnce(&mut User),
+    ) -> Result<(), AuditError> {
+        let before = registry.get(id).ok_or(RegistryError::NotFound(id))?.clone();
+        let mut after = before.clone();
+        change(&mut after);
+        let now = self.clock.now();
+        // 状态转移本身记成 `StatusChanged`，补丁只比它之外的改动
+        let mut moved = before.clone();
+        let status = after.status != before.status;
+        if status {
+            moved.transition(after.status.clone(), now)?;
+            after.status = before.status.clone();
+            after.transition(moved.status.clone(), now)?;
+        }
+        let after = registry.update(id, |user| *user = after)?;
+        let mut patch = UserPatch::diff(&moved, after);
+        if status {
+            let (from, to) = (before.status, moved.status);
+            self.record_at(id, actor, Change::StatusChanged { from, to }, now);
+        }
+        if patch.password.take().is_some() {
+            self.record_at(id, actor, Change::PasswordChanged, now);
+        }
+        if !patch.is_empty() {
+            self.record_at(id, actor, Change::Updated(patch), now);
+        }
+        Ok(())
+    }
+
+    /// 把补丁应用到账户上，记法和 [`AuditLog::update`] 一样。
+    pub fn apply_patch(
+        &mut self,
+        registry: &mut UserRegistry,
+        id: UserId,
+        actor: Actor,
+        patch: &UserPatch,
+    ) -> Result<(), AuditError> {
+        self.update(registry, id, actor, |user| patch.apply(user))
+    }
+
+    /// 改邮箱并记下新旧两个地址；邮箱没变时不记。
+    pub fn change_email(
+        &mut self,
+        registry: &mut UserRegistry,
+        id: UserId,
+        actor: Actor,
+        email: Email,
+    ) -> Result<(), RegistryError> {
+        let from = registry
+            .get(id)
+            .ok_or(RegistryError::NotFound(id))?
+            .email
+            .clone();
+        if from == email {
+            return Ok(());
+        }
+        registry.update(id, |user| user.email = email.clone())?;
+        self.record(id, actor, Change::EmailChanged { from, to: email });
+        Ok(())
+    }
+
+    /// 按状态机的规则转移账户状态并记下新旧状态；不允许的转移返回错误，什么都不记。
+    pub fn change_status(
+        &mut self,
+        registry: &mut UserRegistry,
+        id: UserId,
+        actor: Actor,
+        to: Status,
+    ) -> Result<(), AuditError> {
+        let mut user = registry.get(id).ok_or(RegistryError::NotFound(id))?.clone();
+        let from = user.status.clone();
+        let now = self.clock.now();
+        user.transition(to.clone(), now)?;
+        registry.update(id, |current| *current = user)?;
+        self.record_at(id, actor, Change::StatusChanged { from, to }, now);
+        Ok(())
+    }
+
+    /// 用 `auth` 按用户名登录并记下结果：成功记 `SignedIn`，凭据顺手重算了再记 `PasswordRehashed`；
+    /// 失败时连续失败次数或锁定时间变了就记 `SignInFailed`。失败的尝试不知道是谁做的，记成 `System`。
+    /// 登录成功的事件时间取 `auth` 写进账户的 `last_sign_in`，不另读日志的时钟。
+    pub fn sign_in<A: Clock>(
+        &mut self,
+        registry: &mut UserRegistry,
+        auth: &Authenticator<A>,
+        username: &str,
+        password: &str,
+    ) -> Result<UserId, SignInError> {
+        let Some((id, before)) = registry
+            .find_by_username(username)
+            .map(|(id, user)| (id, user.clone()))
+        else {
+            return auth.sign_in_username(registry, username, password);
+        };
+        let result = auth.sign_in_username(registry, username, password);
+        let after = registry
+            .get(id)
+            .expect("signing in does not remove accounts");
+        match result {
+            Ok(_) => {
+                let rehashed = after.password != before.password;
+                let at = after
+                    .last_sign_in
+                    .expect("signing in sets the last sign-in time");
+                self.record_at(id, Actor::User(id), Change::SignedIn, at);
+                if rehashed {
+                    self.record_at(id, Actor::User(id), Change::PasswordRehashed, at);
+                }
+            }
+            Err(_)
+                if (after.failed_sign_ins, after.locked_until)
+                    != (before.failed_sign_ins, before.locked_until) =>
+            {
+                let change = Change::SignInFailed {
+                    failed_sign_ins: after.failed_sign_ins,
+                    locked_until: after.locked_until,
+                };
+                self.record(id, Actor::System, change);
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    /// `user` 的所有事件，按发生顺序
+    pub fn history(&self, user: UserId) -> impl Iterator<Item = &Event> {
+        self.events.iter().filter(move |event| event.user == user)
+    }
+
+    /// 从头重放所有事件，得到每个账户现在除密码以外的状态。
+    pub fn replay(&self) -> Result<BTreeMap<UserId, User>, ReplayError> {
+        let mut states: BTreeMap<UserId, Option<User>> = BTreeMap::new();
+        for event in &self.events {
+            apply(states.entry(event.user).or_default(), event)?;
+        }
+        Ok(states
+            .into_iter()
+            .filter_map(|(id, user)| Some((id, user?)))
+            .collect())
+    }
+
+    /// 只重放 `user` 的事件；从没创建过时是 `None`。
+    pub fn replay_user(&self, user: UserId) -> Result<Option<User>, ReplayError> {
+        let mut state = None;
+        for event in self.history(user) {
+            apply(&mut state, event)?;
+        }
+        Ok(state)
+    }
+}
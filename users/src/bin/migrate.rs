@@ -0,0 +1,37 @@
+//把用户文件（JSON、TOML 或 CSV，按扩展名判断）里的旧记录升级到当前版本，原子地写回。
+//加 --dry-run 时只列出每条记录会怎么改，不动文件。
+//
+//cargo run -p users --bin migrate -- <文件> [--dry-run]
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use users::migrate::{self, Mode};
+
+fn main() -> ExitCode {
+    let (mut path, mut mode) = (None, Mode::Apply);
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--dry-run" => mode = Mode::DryRun,
+            _ => path = Some(PathBuf::from(arg)),
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("usage: migrate <file.json|file.toml|file.csv> [--dry-run]");
+        return ExitCode::FAILURE;
+    };
+
+    match migrate::migrate_file(&path, mode) {
+        Ok(report) => {
+            print!("{report}");
+            if mode == Mode::DryRun && !report.changes.is_empty() {
+                println!("dry run: {} not modified", path.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}: {e}", path.display());
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! 借用输入的用户
+//!
+//! struct.rs 里那个编译不过的 `User { username: &str, email: &str, .. }` 缺的是生命周期：结构体
+//! 借来的数据必须比结构体活得久。[`UserRef<'a>`] 就是加上生命周期之后的版本，字符串字段都是
+//! 指向输入缓冲区的 `&'a str`，缓冲区不释放它就一直有效。
+//!
+//! [`parse_csv`] 按 [`persist`](crate::persist) 写出的 CSV 格式逐行产出 `UserRef`，只切片不复制，
+//! 批量导入时不用为每个字段分配内存。它只认当前版本的记录，旧版本的行会报 [`ParseError::Version`]，
+//! 需要先用 [`persist::load`](crate::persist::load) 升级。为了做到这一点，它只接受不带转义的字段：被引号括起来的字段
+//! 里如果有 `""`，就没法不复制地还原，这种行会报 [`ParseError::Escaped`]，交给
+//! [`persist::load`](crate::persist::load) 处理。一行就是一条记录，字段里不能有换行。
+//!
+//! 这里只检查格式，状态、邮箱和密码哈希的校验放到 [`UserRef::to_owned`] 转成 `User` 的时候；
+//! 用户名和 [`persist`](crate::persist) 读出来的一样原样接受。
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+
+use crate::email::{Email, EmailError};
+use crate::migrate::CURRENT_VERSION;
+use crate::password::{Credential, PasswordError};
+use crate::persist::CSV_HEADER;
+use crate::status::UnknownStatus;
+use crate::user::User;
+use crate::username::Username;
+
+const FIELDS: usize = 12;
+
+/// 字段借自输入的用户，还没有校验
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct UserRef<'a> {
+    pub status: &'a str,
+    pub username: &'a str,
+    pub email: &'a str,
+    pub sign_in_count: u64,
+    #[serde(borrow)]
+    pub password: Option<&'a str>,
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub last_sign_in: Option<SystemTime>,
+    pub failed_sign_ins: u32,
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub locked_until: Option<SystemTime>,
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub activated_at: Option<SystemTime>,
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub suspended_at: Option<SystemTime>,
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub deleted_at: Option<SystemTime>,
+}
+
+/// [`UserRef`] 转成 `User` 时一个字段校验不通过
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefFieldError {
+    Status(UnknownStatus),
+    Email(EmailError),
+    Password(PasswordError),
+}
+
+impl fmt::Display for RefFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefFieldError::Status(e) => write!(f, "{e}"),
+            RefFieldError::Email(e) => write!(f, "{e}"),
+            RefFieldError::Password(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// [`UserRef::to_owned`] 失败时所有字段的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefError {
+    pub errors: Vec<RefFieldError>,
+}
+
+impl fmt::Display for RefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self.errors.iter().map(ToString::to_string).collect();
+        write!(f, "invalid user record: {}", errors.join("; "))
+    }
+}
+
+impl std::error::Error for RefError {}
+
+impl UserRef<'_> {
+    /// 校验各个字段并复制成自己拥有数据的 `User`，所有字段的错误一起返回。
+    pub fn to_owned(self) -> Result<User, RefError> {
+        let status = self.status.parse().map_err(RefFieldError::Status);
+        let email = Email::parse(self.email).map_err(RefFieldError::Email);
+
+        let password = self
+            .password
+            .map(Credential::parse)
+            .transpose()
+            .map_err(RefFieldError::Password);
+        match (status, email, password) {
+            (Ok(status), Ok(email), Ok(password)) => Ok(User {
+                status,
+                username: Username::from_stored(self.username),
+                email,
+                sign_in_count: self.sign_in_count,
+                password,
+                last_sign_in: self.last_sign_in,
+                failed_sign_ins: self.failed_sign_ins,
+                locked_until: self.locked_until,
+                activated_at: self.activated_at,
+                suspended_at: self.suspended_at,
+                deleted_at: self.deleted_at,
+            }),
+            (status, email, password) => Err(RefError {
+                errors: [status.err(), email.err(), password.err()]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl<'a> TryFrom<UserRef<'a>> for User {
+    type Error = RefError;
+
+    fn try_from(user: UserRef<'a>) -> Result<Self, Self::Error> {
+        user.to_owned()
+    }
+}
+
+/// CSV 格式不对，`line` 从 1 开始，表头是第 1 行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// 第一行不是预期的表头
+    Header,
+    /// 字段数不是 12 个
+    FieldCount { line: usize, found: usize },
+    /// 引号没有闭合，或者闭合的引号后面不是逗号
+    Unterminated { line: usize },
+    /// 带引号的字段里有 `""`，不复制就没法还原
+    Escaped { line: usize },
+    /// 字段的值不是预期的类型
+    InvalidField { line: usize, field: &'static str },
+    /// 不是当前版本的记录
+    Version { line: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Header => write!(f, "line 1: expected header `{CSV_HEADER}`"),
+            ParseError::FieldCount { line, found } => {
+                write!(f, "line {line}: expected {FIELDS} fields, found {found}")
+            }
+            ParseError::Unterminated { line } => write!(f, "line {line}: unterminated quote"),
+            ParseError::Escaped { line } => {
+                write!(f, "line {line}: escaped quotes cannot be borrowed")
+            }
+            ParseError::InvalidField { line, field } => {
+                write!(f, "line {line}: invalid value for `{field}`")
+            }
+            ParseError::Version { line } => {
+                write!(f, "line {line}: not a version {CURRENT_VERSION} record")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// 检查表头，返回逐行产出 `UserRef` 的迭代器。空输入没有表头也没有记录。
+pub fn parse_csv(input: &str) -> Result<CsvUsers<'_>, ParseError> {
+    let mut lines = input.lines();
+    match lines.next() {
+        None => {}
+        Some(header) if header == CSV_HEADER => {}
+        Some(_) => return Err(ParseError::Header),
+    }
+    Ok(CsvUsers { lines, line: 1 })
+}
+
+/// [`parse_csv`] 返回的迭代器
+#[derive(Debug, Clone)]
+pub struct CsvUsers<'a> {
+    lines: std::str::Lines<'a>,
+    line: usize,
+}
+
+impl<'a> Iterator for CsvUsers<'a> {
+    type Item = Result<UserRef<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let text = self.lines.next()?;
+            self.line += 1;
+            if !text.is_empty() {
+                return Some(parse_line(text, self.line));
+            }
+        }
+    }
+}
+
+/// 把一行切成 12 个字段，字段借自 `text`
+fn split(text: &str, line: usize) -> Result<[&str; FIELDS], ParseError> {
+    let mut fields = [""; FIELDS];
+    let mut found = 0;
+    let mut rest = text;
+    loop {
+        let (field, next) = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').ok_or(ParseError::Unterminated { line })?;
+                let after = &quoted[end + 1..];
+                if after.starts_with('"') {
+                    return Err(ParseError::Escaped { line });
+                }
+                match after.strip_prefix(',') {
+                    Some(next) => (&quoted[..end], Some(next)),
+                    None if after.is_empty() => (&quoted[..end], None),
+                    None => return Err(ParseError::Unterminated { line }),
+                }
+            }
+            None => match rest.split_once(',') {
+                Some((field, next)) => (field, Some(next)),
+                None => (rest, None),
+            },
+        };
+        if found < FIELDS {
+            fields[found] = field;
+        }
+        found += 1;
+        match next {
+            Some(next) => rest = next,
+            None => break,
+        }
+    }
+    if found != FIELDS {
+        return Err(ParseError::FieldCount { line, found });
+    }
+    Ok(fields)
+}
+
+/// 空字段是 `None`
+fn optional(value: &str) -> Option<&str> {
+    (!value.is_empty()).then_some(value)
+}
+
+fn parse_line(text: &str, line: usize) -> Result<UserRef<'_>, ParseError> {
+    let [version, status, username, email, sign_in_count, password, last_sign_in, failed_sign_ins, locked_until, activated_at, suspended_at, deleted_at] =
+        split(text, line)?;
+    if version.parse() != Ok(CURRENT_VERSION) {
+        return Err(ParseError::Version { line });
+    }
+    let invalid = |field| ParseError::InvalidField { line, field };
+    let time = |value: &str, field| match optional(value) {
+        None => Ok(None),
+        Some(secs) => secs
+            .parse()
+            .map(|secs| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)))
+            .map_err(|_| invalid(field)),
+    };
+    Ok(UserRef {
+        status,
+        username,
+        email,
+        sign_in_count: sign_in_count
+            .parse()
+            .map_err(|_| invalid("sign_in_count"))?,
+        password: optional(password),
+        last_sign_in: time(last_sign_in, "last_sign_in")?,
+        failed_sign_ins: failed_sign_ins
+            .parse()
+            .map_err(|_| invalid("failed_sign_ins"))?,
+        locked_until: time(locked_until, "locked_until")?,
+        activated_at: time(activated_at, "activated_at")?,
+        suspended_at: time(suspended_at, "suspended_at")?,
+        deleted_at: time(deleted_at, "deleted_at")?,
+    })
+}
@@ -0,0 +1,174 @@
+//! 分步构造用户
+//!
+//! [`build_user`](crate::build_user) 只收邮箱和用户名，`status` 和 `sign_in_count` 写死了。
+//! [`UserBuilder`] 把其余字段也开放出来，没设置的用默认值。邮箱和用户名是必填的，用类型状态保证：
+//! 两个类型参数分别记录邮箱和用户名有没有给过，只有 `UserBuilder<Set, Set>` 才有 `build`，
+//! 漏填的话编译不过：
+//!
+//! ```compile_fail,E0599
+//! let user = users::UserBuilder::new().email("alice@example.com").build();
+//! ```
+//!
+//! 邮箱和用户名以字符串传入，到 `build` 时才校验，所有错误一起放进 [`BuildError`] 返回，
+//! 不会改完一个再报下一个。
+
+use std::fmt;
+
+use crate::email::{Email, EmailError};
+use crate::password::Credential;
+use crate::status::Status;
+use crate::user::User;
+use crate::username::{UsernameError, UsernamePolicy};
+
+/// 必填字段还没给
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unset;
+
+/// 必填字段给过了，值到 `build` 时再校验
+#[derive(Debug, Clone)]
+pub struct Set(String);
+
+/// 一个字段校验不通过
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldError {
+    Email(EmailError),
+    Username(UsernameError),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::Email(e) => write!(f, "{e}"),
+            FieldError::Username(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// `build` 失败时所有字段的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildError {
+    pub errors: Vec<FieldError>,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self.errors.iter().map(ToString::to_string).collect();
+        write!(f, "invalid user: {}", errors.join("; "))
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// `User` 的构造器，`E` 和 `U` 记录邮箱和用户名是否已经给过
+#[derive(Debug, Clone)]
+pub struct UserBuilder<E = Unset, U = Unset> {
+    email: E,
+    username: U,
+    status: Status,
+    sign_in_count: u64,
+    password: Option<Credential>,
+    policy: UsernamePolicy,
+}
+
+impl Default for UserBuilder {
+    fn default() -> Self {
+        UserBuilder {
+            email: Unset,
+            username: Unset,
+            status: Status::Active,
+            sign_in_count: 1,
+            password: None,
+            policy: UsernamePolicy::default(),
+        }
+    }
+}
+
+impl UserBuilder {
+    /// 默认值和 `build_user` 一样：`Active`、登录次数 1、没有密码，用户名按默认策略校验。
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<U> UserBuilder<Unset, U> {
+    pub fn email(self, email: impl Into<String>) -> UserBuilder<Set, U> {
+        UserBuilder {
+            email: Set(email.into()),
+            username: self.username,
+            status: self.status,
+            sign_in_count: self.sign_in_count,
+            password: self.password,
+            policy: self.policy,
+        }
+    }
+}
+
+impl<E> UserBuilder<E, Unset> {
+    pub fn username(self, username: impl Into<String>) -> UserBuilder<E, Set> {
+        UserBuilder {
+            email: self.email,
+            username: Set(username.into()),
+            status: self.status,
+            sign_in_count: self.sign_in_count,
+            password: self.password,
+            policy: self.policy,
+        }
+    }
+}
+
+impl<E, U> UserBuilder<E, U> {
+    /// 初始状态，例如要先验证邮箱的账户从 `Pending` 开始
+    pub fn status(mut self, status: Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn sign_in_count(mut self, count: u64) -> Self {
+        self.sign_in_count = count;
+        self
+    }
+
+    /// 已经算好的密码哈希，见 [`Kdf::hash`](crate::Kdf::hash)
+    pub fn password(mut self, credential: Credential) -> Self {
+        self.password = Some(credential);
+        self
+    }
+
+    /// 用别的策略校验用户名。策略只在这里用一次，存下来的用户读回时不再检查用户名，见 [`Username`](crate::Username)。
+    pub fn policy(mut self, policy: UsernamePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl UserBuilder<Set, Set> {
+    /// 校验邮箱和用户名，都通过才构造出 `User`，否则一次返回所有错误。
+    pub fn build(self) -> Result<User, BuildError> {
+        let email = Email::parse(&self.email.0).map_err(FieldError::Email);
+        let username = self
+            .policy
+            .parse(&self.username.0)
+            .map_err(FieldError::Username);
+        match (email, username) {
+            (Ok(email), Ok(username)) => Ok(User {
+                status: self.status,
+                username,
+                email,
+                sign_in_count: self.sign_in_count,
+                password: self.password,
+                last_sign_in: None,
+                failed_sign_ins: 0,
+                locked_until: None,
+                activated_at: None,
+                suspended_at: None,
+                deleted_at: None,
+            }),
+            (email, username) => Err(BuildError {
+                errors: [email.err(), username.err()]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            }),
+        }
+    }
+}
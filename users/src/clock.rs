@@ -0,0 +1,93 @@
+//! 时间来源
+//!
+//! 登录退避、会话过期都要看“现在几点”。把它抽成 [`Clock`]，生产环境用 [`SystemClock`]，
+//! 测试里用 [`ManualClock`] 手动拨时间，过期逻辑就能确定地测试。
+
+use std::cell::Cell;
+use std::time::{Duration, SystemTime};
+
+/// `now` 之后 `delay` 的时刻；超出 `SystemTime` 能表示的范围时取能表示的最远时刻
+pub(crate) fn later(now: SystemTime, mut delay: Duration) -> SystemTime {
+    loop {
+        match now.checked_add(delay) {
+            Some(time) => return time,
+            None => delay /= 2,
+        }
+    }
+}
+
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+/// 系统时间
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// 只在调用 [`advance`](ManualClock::advance) 或 [`set`](ManualClock::set) 时才走的时钟
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Cell<SystemTime>,
+}
+
+impl ManualClock {
+    pub fn new(start: SystemTime) -> Self {
+        ManualClock {
+            now: Cell::new(start),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        self.now.set(now);
+    }
+}
+
+impl Default for ManualClock {
+    /// 从 Unix 纪元开始
+    fn default() -> Self {
+        ManualClock::new(SystemTime::UNIX_EPOCH)
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.now.get()
+    }
+}
+
+/// `Option<SystemTime>` 按 Unix 秒数序列化，JSON、TOML、CSV 里都是一个整数
+pub(crate) mod unix_seconds {
+    use std::time::{Duration, SystemTime};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &Option<SystemTime>, s: S) -> Result<S::Ok, S::Error> {
+        time.map(|time| {
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        })
+        .serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<SystemTime>, D::Error> {
+        let secs = Option::<u64>::deserialize(d)?;
+        Ok(secs.map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)))
+    }
+}
@@ -0,0 +1,193 @@
+//! 邮箱地址
+//!
+//! [`Email`] 只能通过解析得到，所以拿到一个 `Email` 就说明它是合法的地址。语法按 RFC 5322 的 addr-spec：
+//! `local-part "@" domain`，本地部分是 dot-atom 或者带引号的字符串；按 RFC 6532 允许本地部分出现 UTF-8 字符。
+//! 域名是 dot-atom 或者 `[...]` 形式的地址字面量，dot-atom 形式的域名再按 IDNA（UTS #46）检查，
+//! 统一成小写的 Unicode 形式，[`Email::ascii_domain`] 给出 Punycode 形式。
+//!
+//! 不支持地址里的注释和折行（CFWS），也不支持 obs- 开头的过时语法。
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// RFC 5321 对本地部分和整个地址的长度限制（按字节）
+const MAX_LOCAL_LEN: usize = 64;
+const MAX_LEN: usize = 254;
+
+/// 邮箱地址不合法的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmailError {
+    Empty,
+    MissingAt,
+    InvalidLocalPart(String),
+    InvalidDomain(String),
+    LocalPartTooLong,
+    TooLong,
+}
+
+impl fmt::Display for EmailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmailError::Empty => write!(f, "email address is empty"),
+            EmailError::MissingAt => write!(f, "email address has no `@`"),
+            EmailError::InvalidLocalPart(local) => write!(f, "invalid local part `{local}`"),
+            EmailError::InvalidDomain(domain) => write!(f, "invalid domain `{domain}`"),
+            EmailError::LocalPartTooLong => {
+                write!(f, "local part is longer than {MAX_LOCAL_LEN} bytes")
+            }
+            EmailError::TooLong => write!(f, "email address is longer than {MAX_LEN} bytes"),
+        }
+    }
+}
+
+impl std::error::Error for EmailError {}
+
+/// 一个合法的邮箱地址，域名已经规范化；序列化成字符串，反序列化时重新校验
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Email {
+    address: String,
+    /// `@` 在 `address` 里的位置
+    at: usize,
+}
+
+/// RFC 5322 的 atext，加上 RFC 6532 的非 ASCII 字符
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c) || !c.is_ascii()
+}
+
+/// `1*atext *("." 1*atext)`
+fn is_dot_atom(text: &str) -> bool {
+    text.split('.')
+        .all(|atom| !atom.is_empty() && atom.chars().all(is_atext))
+}
+
+/// `DQUOTE *(qtext / quoted-pair) DQUOTE`，引号内允许空格和制表符
+fn is_quoted_string(text: &str) -> bool {
+    let Some(inner) = text
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    else {
+        return false;
+    };
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) if c == ' ' || c == '\t' || c.is_ascii_graphic() || !c.is_ascii() => {}
+                _ => return false,
+            },
+            '"' => return false,
+            ' ' | '\t' => {}
+            c if c.is_ascii_graphic() || !c.is_ascii() => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// `[` 地址 `]`：dtext 是除了 `[`、`]`、`\` 以外的可见 ASCII 字符
+fn is_domain_literal(text: &str) -> bool {
+    text.strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .is_some_and(|inner| {
+            inner
+                .chars()
+                .all(|c| c.is_ascii_graphic() && !"[]\\".contains(c))
+        })
+}
+
+fn normalize_domain(domain: &str) -> Result<String, EmailError> {
+    let invalid = || EmailError::InvalidDomain(domain.to_string());
+    if is_domain_literal(domain) {
+        return Ok(domain.to_ascii_lowercase());
+    }
+    if domain.split('.').any(str::is_empty) {
+        return Err(invalid());
+    }
+    let ascii = idna::domain_to_ascii_strict(domain).map_err(|_| invalid())?;
+    let (unicode, result) = idna::domain_to_unicode(&ascii);
+    result.map_err(|_| invalid())?;
+    Ok(unicode)
+}
+
+impl Email {
+    /// 解析并规范化一个地址。
+    pub fn parse(address: &str) -> Result<Self, EmailError> {
+        if address.is_empty() {
+            return Err(EmailError::Empty);
+        }
+        // 带引号的本地部分里也可以有 `@`，所以从右边找
+        let (local, domain) = address.rsplit_once('@').ok_or(EmailError::MissingAt)?;
+        if !(is_dot_atom(local) || is_quoted_string(local)) {
+            return Err(EmailError::InvalidLocalPart(local.to_string()));
+        }
+        if local.len() > MAX_LOCAL_LEN {
+            return Err(EmailError::LocalPartTooLong);
+        }
+        let domain = normalize_domain(domain)?;
+        let address = format!("{local}@{domain}");
+        if address.len() > MAX_LEN {
+            return Err(EmailError::TooLong);
+        }
+        Ok(Email {
+            at: local.len(),
+            address,
+        })
+    }
+
+    /// `@` 前面的部分，原样保留大小写和引号
+    pub fn local_part(&self) -> &str {
+        &self.address[..self.at]
+    }
+
+    /// `@` 后面的部分，小写的 Unicode 形式
+    pub fn domain(&self) -> &str {
+        &self.address[self.at + 1..]
+    }
+
+    /// 域名的 ASCII 形式，国际化域名转成 Punycode（`xn--`）
+    pub fn ascii_domain(&self) -> String {
+        idna::domain_to_ascii(self.domain()).unwrap_or_else(|_| self.domain().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.address
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.address)
+    }
+}
+
+impl FromStr for Email {
+    type Err = EmailError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Email::parse(s)
+    }
+}
+
+impl TryFrom<String> for Email {
+    type Error = EmailError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Email::parse(&s)
+    }
+}
+
+impl From<Email> for String {
+    fn from(email: Email) -> Self {
+        email.address
+    }
+}
+
+impl AsRef<str> for Email {
+    fn as_ref(&self) -> &str {
+        &self.address
+    }
+}
@@ -0,0 +1,55 @@
+//! 账户
+//!
+//! 从 rust/struct.rs 里的 `User` 和 `build_user` 长出来的用户账户模型：笔记里只有一个结构体，
+//! 这里补上真正用起来需要的部分。
+//!
+//! [`User`] 和 [`build_user`] 见 [`user`]，要设置更多字段时用 [`UserBuilder`]，见 [`builder`]；
+//! 账户的生命周期 [`Status`] 见 [`status`]；对已有用户的部分修改是 [`UserPatch`]，见 [`patch`]；
+//! 字段借自输入缓冲区的 [`UserRef`] 见 [`borrowed`]。
+//! 邮箱是校验过的 [`Email`]，见 [`email`]；
+//! 用户名要通过 [`UsernamePolicy`]，见 [`username`]；密码存成 Argon2 哈希，见 [`password`]。
+//! [`UserRegistry`] 持有所有账户，保证用户名和邮箱不重复，见 [`registry`]。
+//! [`Authenticator`] 负责登录、失败退避和锁定，见 [`signin`]；需要时间的地方都通过 [`Clock`]，
+//! 见 [`clock`]。登录后发放的会话令牌和它们的过期见 [`session`]。每次修改记进只追加的 [`AuditLog`]，
+//! 见 [`audit`]。
+//! 用户可以存成 JSON、TOML 或 CSV 文件，见 [`persist`]；带版本号的记录和旧格式的升级见 [`migrate`]。
+//! 应用里用的 [`Storage`] 有内存和 SQLite 两种实现，见 [`storage`] 和 [`sqlite`]。
+//! 角色、权限和从策略文件读出的 [`Policy`] 见 [`access`]，检查权限用 [`User::can`]。
+
+pub mod access;
+pub mod audit;
+pub mod borrowed;
+pub mod builder;
+pub mod clock;
+pub mod email;
+pub mod migrate;
+pub mod password;
+pub mod patch;
+pub mod persist;
+pub mod registry;
+pub mod session;
+pub mod signin;
+pub mod sqlite;
+pub mod status;
+pub mod storage;
+pub mod user;
+pub mod username;
+
+pub use access::{Permission, Policy, PolicyError, Role};
+pub use audit::{Actor, AuditError, AuditLog, Change, Event, ReplayError};
+pub use borrowed::{ParseError, RefError, RefFieldError, UserRef};
+pub use builder::{BuildError, FieldError, UserBuilder};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use email::{Email, EmailError};
+pub use migrate::{Envelope, MigrateError, Mode};
+pub use password::{Credential, Kdf, PasswordError};
+pub use patch::UserPatch;
+pub use persist::{Format, PersistError};
+pub use registry::{RegistryError, UserId, UserRegistry};
+pub use session::{Session, SessionError, SessionPolicy, SessionStore, Token};
+pub use signin::{Authenticator, SignInError, SignInPolicy};
+pub use sqlite::SqliteStorage;
+pub use status::{Status, TransitionError, UnknownStatus};
+pub use storage::{MemoryStorage, Storage, StorageError};
+pub use user::{build_user, User};
+pub use username::{CharClass, Username, UsernameError, UsernamePolicy, Violation};
@@ -0,0 +1,255 @@
+//! 存储格式的版本和迁移
+//!
+//! `User` 会继续长字段，旧文件里的记录就反序列化不了了。[`persist`] 把每条记录都装进
+//! 带版本号的信封：JSON 里是 `{"version": 4, "record": {...}}`，TOML 里是带 `version` 和 `record`
+//! 子表的 `[[users]]`，CSV 里是第一列 `version`。读的时候沿着迁移链一步步升级到当前版本再解码。
+//!
+//! | 版本 | 形状 |
+//! |------|------|
+//! | 1 | struct.rs 里的 `{active, username, email, sign_in_count}` |
+//! | 2 | 加上 `password` |
+//! | 3 | 加上 `last_sign_in`、`failed_sign_ins`、`locked_until` |
+//! | 4 | `active` 换成 [`Status`](crate::Status)，加上 `activated_at`、`suspended_at`、`deleted_at` |
+//!
+//! 没有信封的裸记录按它有哪些字段推断版本：有 `status` 的就是当前版本，只有 struct.rs 那几个字段的是
+//! 版本 1。迁移只补缺少的字段，已经有的字段不动。停用的账户（`active: false`）升级后是
+//! `suspended: deactivated`。[`Mode::DryRun`] 只报告会改什么，不写文件。
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::persist::{self, Format, PersistError};
+use crate::user::User;
+
+/// 当前的记录版本
+pub const CURRENT_VERSION: u32 = 4;
+
+/// 带版本号的记录；读的时候 `record` 是还没升级的 JSON 值，写的时候是 `&User`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope<T = Value> {
+    pub version: u32,
+    pub record: T,
+}
+
+/// 迁移失败的原因，`record` 是记录在文件里的下标
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrateError {
+    /// 版本号比当前的还新，或者不认识
+    UnsupportedVersion { record: usize, version: u32 },
+    /// 记录不是对象，或者升级后解码失败
+    Invalid { record: usize, message: String },
+}
+
+impl fmt::Display for MigrateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrateError::UnsupportedVersion { record, version } => write!(
+                f,
+                "record {record}: unsupported version {version} (current is {CURRENT_VERSION})"
+            ),
+            MigrateError::Invalid { record, message } => write!(f, "record {record}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for MigrateError {}
+
+/// 从 `from` 版本升到下一个版本
+struct Migration {
+    from: u32,
+    apply: fn(&mut Map<String, Value>, &mut Vec<String>),
+}
+
+/// 按版本顺序排列，每个版本一步
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        apply: |record, edits| add_field(record, "password", Value::Null, edits),
+    },
+    Migration {
+        from: 2,
+        apply: |record, edits| {
+            add_field(record, "last_sign_in", Value::Null, edits);
+            add_field(record, "failed_sign_ins", Value::from(0), edits);
+            add_field(record, "locked_until", Value::Null, edits);
+        },
+    },
+    Migration {
+        from: 3,
+        apply: |record, edits| {
+            if let Some(active) = record.remove("active") {
+                let status = match active {
+                    Value::Bool(false) => "suspended: deactivated",
+                    _ => "active",
+                };
+                edits.push(format!(
+                    "replace `active` = {active} with `status` = \"{status}\""
+                ));
+                record.insert("status".into(), Value::from(status));
+            }
+            add_field(record, "activated_at", Value::Null, edits);
+            add_field(record, "suspended_at", Value::Null, edits);
+            add_field(record, "deleted_at", Value::Null, edits);
+        },
+    },
+];
+
+fn add_field(record: &mut Map<String, Value>, name: &str, value: Value, edits: &mut Vec<String>) {
+    if !record.contains_key(name) {
+        edits.push(format!("add `{name}` = {value}"));
+        record.insert(name.to_string(), value);
+    }
+}
+
+/// 一条记录的升级
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub record: usize,
+    pub username: String,
+    pub from: u32,
+    pub to: u32,
+    /// 具体改了哪些字段
+    pub edits: Vec<String>,
+}
+
+/// 迁移的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// 需要升级的记录；已经是当前版本的不列出
+    pub changes: Vec<Change>,
+    pub users: Vec<User>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(
+                f,
+                "all {} records are at version {CURRENT_VERSION}",
+                self.users.len()
+            );
+        }
+        for change in &self.changes {
+            writeln!(
+                f,
+                "record {} ({}): v{} -> v{}",
+                change.record, change.username, change.from, change.to
+            )?;
+            for edit in &change.edits {
+                writeln!(f, "  {edit}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 迁移文件时是只报告还是真的写回去
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    DryRun,
+    Apply,
+}
+
+/// 把一条记录升级到当前版本，返回升级后的信封和改动说明。
+pub fn upgrade(envelope: Envelope, record: usize) -> Result<(Envelope, Vec<String>), MigrateError> {
+    let Envelope {
+        version,
+        record: value,
+    } = envelope;
+    if version == 0 || version > CURRENT_VERSION {
+        return Err(MigrateError::UnsupportedVersion { record, version });
+    }
+    let Value::Object(mut fields) = value else {
+        return Err(MigrateError::Invalid {
+            record,
+            message: "record is not an object".into(),
+        });
+    };
+    let mut edits = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= version) {
+        (migration.apply)(&mut fields, &mut edits);
+    }
+    Ok((
+        Envelope {
+            version: CURRENT_VERSION,
+            record: Value::Object(fields),
+        },
+        edits,
+    ))
+}
+
+/// 每个版本新加的字段之一，按版本从新到旧排列；裸记录有哪个就至少是哪个版本
+const SHAPES: &[(u32, &str)] = &[(4, "status"), (3, "failed_sign_ins"), (2, "password")];
+
+/// 拆开信封；没有信封的裸记录按形状推断版本
+fn envelope(value: Value) -> Result<Envelope, Value> {
+    let Value::Object(mut fields) = value else {
+        return Err(value);
+    };
+    if fields.len() == 2 && fields.contains_key("record") {
+        if let Some(version) = fields.get("version").and_then(Value::as_u64) {
+            return Ok(Envelope {
+                version: u32::try_from(version).unwrap_or(u32::MAX),
+                record: fields.remove("record").expect("checked above"),
+            });
+        }
+    }
+    let version = SHAPES
+        .iter()
+        .find(|(_, field)| fields.contains_key(*field))
+        .map_or(1, |(version, _)| *version);
+    Ok(Envelope {
+        version,
+        record: Value::Object(fields),
+    })
+}
+
+/// 把读出来的记录全部升级到当前版本并解码。
+pub fn upgrade_all(values: Vec<Value>) -> Result<Report, MigrateError> {
+    let mut report = Report {
+        changes: Vec::new(),
+        users: Vec::new(),
+    };
+    for (index, value) in values.into_iter().enumerate() {
+        let old = envelope(value).map_err(|_| MigrateError::Invalid {
+            record: index,
+            message: "record is not an object".into(),
+        })?;
+        let from = old.version;
+        let (new, edits) = upgrade(old, index)?;
+        let user: User = serde_json::from_value(new.record).map_err(|e| MigrateError::Invalid {
+            record: index,
+            message: e.to_string(),
+        })?;
+        if from != CURRENT_VERSION || !edits.is_empty() {
+            report.changes.push(Change {
+                record: index,
+                username: user.username.to_string(),
+                from,
+                to: CURRENT_VERSION,
+                edits,
+            });
+        }
+        report.users.push(user);
+    }
+    Ok(report)
+}
+
+/// 解析 `format` 格式的文本，把记录全部升级到当前版本并解码。
+pub fn load(text: &str, format: Format) -> Result<Report, PersistError> {
+    Ok(upgrade_all(persist::records(text, format)?)?)
+}
+
+/// 升级一个用户文件，格式按扩展名判断。`Mode::Apply` 且确实有改动时用 [`persist::save`] 原子地写回，
+/// `Mode::DryRun` 只返回报告。
+pub fn migrate_file(path: &Path, mode: Mode) -> Result<Report, PersistError> {
+    let report = load(&fs::read_to_string(path)?, persist::format_of(path)?)?;
+    if mode == Mode::Apply && !report.changes.is_empty() {
+        persist::save(path, &report.users)?;
+    }
+    Ok(report)
+}
@@ -0,0 +1,195 @@
+//! 密码凭据
+//!
+//! 密码从不明文保存：[`Kdf`] 用 Argon2id 这种耗内存的密钥派生函数加随机盐算出哈希，存成自描述的
+//! PHC 字符串（`$argon2id$v=19$m=...,t=...,p=...$盐$哈希`），算法和参数都记在里面。验证时按字符串里
+//! 记下的参数重新计算，用常量时间比较结果，不会因为比较提前结束而泄露哈希内容。
+//!
+//! 参数会随着硬件变快而调高。验证成功时如果发现凭据还是旧参数算的，就顺手用新参数重新算一份，
+//! 用户下次登录时就换成了新的哈希，不用强制改密码。
+
+use std::fmt;
+
+use argon2::password_hash::PasswordHasher;
+use argon2::{Algorithm, Argon2, Params, PasswordHash, Version};
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+
+/// 密码相关操作失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordError {
+    /// 账户还没有设置密码
+    NoPassword,
+    /// 密码不对
+    Mismatch,
+    /// 保存的凭据不是合法的 Argon2 PHC 字符串
+    InvalidHash(String),
+    /// 参数不合法或者计算失败
+    Kdf(String),
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordError::NoPassword => write!(f, "no password is set"),
+            PasswordError::Mismatch => write!(f, "password does not match"),
+            PasswordError::InvalidHash(reason) => write!(f, "invalid password hash: {reason}"),
+            PasswordError::Kdf(reason) => write!(f, "password hashing failed: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for PasswordError {}
+
+fn invalid(e: impl fmt::Display) -> PasswordError {
+    PasswordError::InvalidHash(e.to_string())
+}
+
+fn kdf_error(e: impl fmt::Display) -> PasswordError {
+    PasswordError::Kdf(e.to_string())
+}
+
+/// 保存下来的密码哈希，PHC 格式；序列化成 PHC 字符串
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Credential(String);
+
+impl Credential {
+    /// 从保存的 PHC 字符串恢复，只接受 Argon2 的哈希。
+    pub fn parse(phc: &str) -> Result<Self, PasswordError> {
+        decode(phc)?;
+        Ok(Credential(phc.to_string()))
+    }
+
+    pub fn as_phc(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Credential {
+    type Error = PasswordError;
+
+    fn try_from(phc: String) -> Result<Self, Self::Error> {
+        Credential::parse(&phc)
+    }
+}
+
+impl From<Credential> for String {
+    fn from(credential: Credential) -> Self {
+        credential.0
+    }
+}
+
+/// 只显示算法和参数，不显示盐和哈希
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head: Vec<&str> = self.0.split('$').take(4).collect();
+        write!(f, "Credential({}$...)", head.join("$"))
+    }
+}
+
+/// PHC 字符串里的算法、版本和参数
+fn decode(phc: &str) -> Result<(PasswordHash, Algorithm, Version, Params), PasswordError> {
+    let hash = PasswordHash::new(phc).map_err(invalid)?;
+    let algorithm = Algorithm::try_from(hash.algorithm.as_str()).map_err(invalid)?;
+    let version = match hash.version {
+        Some(version) => Version::try_from(version).map_err(invalid)?,
+        None => Version::V0x10,
+    };
+    let params = Params::try_from(&hash).map_err(invalid)?;
+    if hash.salt.is_none() || hash.hash.is_none() {
+        return Err(invalid("missing salt or hash"));
+    }
+    Ok((hash, algorithm, version, params))
+}
+
+/// [`Kdf::verify_dummy`] 用的盐
+const DUMMY_SALT: &[u8] = b"no-such-account.";
+
+/// 当前使用的哈希参数
+#[derive(Debug, Clone)]
+pub struct Kdf {
+    params: Params,
+}
+
+impl Default for Kdf {
+    /// Argon2id 的推荐参数：19 MiB 内存、2 轮、1 路并行。
+    fn default() -> Self {
+        Kdf {
+            params: Params::default(),
+        }
+    }
+}
+
+impl Kdf {
+    /// `memory_kib` 是用到的内存（KiB），`iterations` 是轮数，`parallelism` 是并行度。
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> Result<Self, PasswordError> {
+        let params = Params::new(memory_kib, iterations, parallelism, None).map_err(kdf_error)?;
+        Ok(Kdf { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+
+    /// 用随机盐算出 `password` 的哈希。
+    pub fn hash(&self, password: &str) -> Result<Credential, PasswordError> {
+        let hash: PasswordHash = self
+            .argon2()
+            .hash_password(password.as_bytes())
+            .map_err(kdf_error)?;
+        Ok(Credential(hash.to_string()))
+    }
+
+    /// 凭据是不是用别的算法或参数算的
+    pub fn needs_rehash(&self, credential: &Credential) -> bool {
+        let Ok((_, algorithm, version, params)) = decode(credential.as_phc()) else {
+            return true;
+        };
+        let output_len =
+            |params: &Params| params.output_len().unwrap_or(Params::DEFAULT_OUTPUT_LEN);
+        algorithm != Algorithm::Argon2id
+            || version != Version::V0x13
+            || params.m_cost() != self.params.m_cost()
+            || params.t_cost() != self.params.t_cost()
+            || params.p_cost() != self.params.p_cost()
+            || output_len(&params) != output_len(&self.params)
+    }
+
+    /// 验证密码。密码正确且凭据需要按当前参数重算时，返回新的凭据。
+    pub fn verify(
+        &self,
+        credential: &Credential,
+        password: &str,
+    ) -> Result<Option<Credential>, PasswordError> {
+        let (hash, algorithm, version, params) = decode(credential.as_phc())?;
+        let (Some(salt), Some(expected)) = (&hash.salt, &hash.hash) else {
+            return Err(invalid("missing salt or hash"));
+        };
+        let mut actual = vec![0u8; expected.len()];
+        Argon2::new(algorithm, version, params)
+            .hash_password_into(password.as_bytes(), salt, &mut actual)
+            .map_err(kdf_error)?;
+        if !bool::from(actual.ct_eq(expected.as_bytes())) {
+            return Err(PasswordError::Mismatch);
+        }
+        if self.needs_rehash(credential) {
+            self.hash(password).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 按当前参数对一个固定的盐算一遍哈希，结果丢掉。账户不存在时用它代替 [`Kdf::verify`]，
+    /// 两种情况花的时间差不多，不会从响应快慢看出账户是否存在。
+    pub fn verify_dummy(&self, password: &str) {
+        let mut output = vec![
+            0u8;
+            self.params
+                .output_len()
+                .unwrap_or(Params::DEFAULT_OUTPUT_LEN)
+        ];
+        let _ = self
+            .argon2()
+            .hash_password_into(password.as_bytes(), DUMMY_SALT, &mut output);
+    }
+}
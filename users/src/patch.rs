@@ -0,0 +1,171 @@
+//! 部分更新
+//!
+//! struct.rs 用结构体更新语法 `User { email: ..., ..user1 }` 造新用户，顺带把 `user1.username`
+//! 移走了，`user1` 之后就不能整体使用。[`UserPatch`] 把“改哪些字段、改成什么”变成一个独立的值：
+//! 每个字段都是 `Option`，`None` 表示不改；本来就是 `Option` 的字段（比如 `password`）在补丁里是
+//! `Option<Option<_>>`，`Some(None)` 表示清空。
+//!
+//! 补丁通过 `&mut User` 原地应用，只克隆补丁里的值，不会从任何用户身上移走字段。补丁可以合并、
+//! 求逆（撤销）、从两个用户算出来，也能序列化后在网络上传递：没改的字段不出现，反序列化时邮箱会重新校验。
+//! 用户名和账户里存的一样原样读回，新起的名字在造补丁时用 [`Username::parse`] 或
+//! [`UsernamePolicy::parse`](crate::UsernamePolicy::parse) 检查。
+//!
+//! 补丁里没有 `status`：状态只能按状态机转移，撤销和求差却要能回到任意状态，两者合不到一起。
+//! 改状态请用 [`User::transition`]，带着 `status` 的补丁反序列化时会被拒绝。
+
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::email::Email;
+use crate::password::Credential;
+use crate::user::User;
+use crate::username::Username;
+
+/// 字段出现就是 `Some`，值本身可以是 `null`
+mod present {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T: Serialize, S: Serializer>(
+        value: &Option<Option<T>>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.as_ref().and_then(Option::as_ref).serialize(s)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<Option<T>>, D::Error> {
+        Option::<T>::deserialize(d).map(Some)
+    }
+}
+
+/// 同上，时间按 Unix 秒数
+mod present_unix_seconds {
+    use std::time::SystemTime;
+
+    use serde::{Deserializer, Serializer};
+
+    use crate::clock::unix_seconds;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Option<SystemTime>>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        unix_seconds::serialize(&value.flatten(), s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<Option<SystemTime>>, D::Error> {
+        unix_seconds::deserialize(d).map(Some)
+    }
+}
+
+/// 对一个用户的一组修改，`None` 的字段不动
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserPatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<Username>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<Email>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign_in_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "present")]
+    pub password: Option<Option<Credential>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "present_unix_seconds"
+    )]
+    pub last_sign_in: Option<Option<SystemTime>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_sign_ins: Option<u32>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "present_unix_seconds"
+    )]
+    pub locked_until: Option<Option<SystemTime>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "present_unix_seconds"
+    )]
+    pub activated_at: Option<Option<SystemTime>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "present_unix_seconds"
+    )]
+    pub suspended_at: Option<Option<SystemTime>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "present_unix_seconds"
+    )]
+    pub deleted_at: Option<Option<SystemTime>>,
+}
+
+/// 每个方法对所有字段做同一件事，字段名列一遍就够了
+macro_rules! patch_methods {
+    ($($field:ident),*) => {
+        impl UserPatch {
+            /// 什么都不改
+            pub fn is_empty(&self) -> bool {
+                $(self.$field.is_none())&&*
+            }
+
+            /// 要改的字段名，按声明顺序
+            pub fn fields(&self) -> Vec<&'static str> {
+                let mut fields = Vec::new();
+                $(if self.$field.is_some() {
+                    fields.push(stringify!($field));
+                })*
+                fields
+            }
+
+            /// 原地修改 `user`，只克隆补丁里的值。
+            pub fn apply(&self, user: &mut User) {
+                $(if let Some(value) = &self.$field {
+                    user.$field = value.clone();
+                })*
+            }
+
+            /// 先应用 `self` 再应用 `later` 的效果；两边都改了的字段以 `later` 为准。
+            pub fn merge(self, later: UserPatch) -> UserPatch {
+                UserPatch {
+                    $($field: later.$field.or(self.$field),)*
+                }
+            }
+
+            /// 撤销补丁：把 `self` 应用到 `original` 上之后，再应用返回的补丁就回到 `original`。
+            pub fn invert(&self, original: &User) -> UserPatch {
+                UserPatch {
+                    $($field: self.$field.as_ref().map(|_| original.$field.clone()),)*
+                }
+            }
+
+            /// 把 `old` 变成 `new` 的最小补丁，只包含不相同的字段。
+            pub fn diff(old: &User, new: &User) -> UserPatch {
+                UserPatch {
+                    $($field: (old.$field != new.$field).then(|| new.$field.clone()),)*
+                }
+            }
+        }
+    };
+}
+
+patch_methods!(
+    username,
+    email,
+    sign_in_count,
+    password,
+    last_sign_in,
+    failed_sign_ins,
+    locked_until,
+    activated_at,
+    suspended_at,
+    deleted_at
+);
@@ -0,0 +1,282 @@
+//! 把用户存成文件
+//!
+//! 支持 JSON、TOML 和 CSV 三种格式，按文件扩展名选择。每条记录都带着版本号，见 [`migrate`]：JSON 是
+//! [`Envelope`] 数组；TOML 顶层必须是表，所以写成 `[[users]]` 数组表，每个表有 `version` 和 `record`；
+//! CSV 每行一个用户，第一行是字段名，第一列是 `version`，没有值的字段留空。读的时候先把旧版本的记录
+//! 升级到当前版本，旧文件和没有版本号的裸记录也能直接读。
+//!
+//! [`save`] 先写到同目录下的临时文件、刷到磁盘，再改名成目标文件。改名是原子的，中途崩溃时
+//! 目标文件要么是旧内容要么是新内容，不会只写了一半。临时文件名带进程号和进程内的序号，几个线程同时
+//! 保存同一个文件也不会写进同一个临时文件；改名之后再把目录刷到磁盘，改名本身也不会因为断电丢掉。
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::migrate::{self, Envelope, MigrateError, CURRENT_VERSION};
+use crate::user::User;
+
+/// 文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Csv,
+}
+
+impl Format {
+    /// 按扩展名判断格式：`.json`、`.toml`、`.csv`
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// 读写失败的原因
+#[derive(Debug)]
+pub enum PersistError {
+    Io(io::Error),
+    /// 编码或者解码失败，包括字段校验不通过（例如不合法的邮箱）
+    Format {
+        format: Format,
+        message: String,
+    },
+    UnknownFormat(PathBuf),
+    /// 记录升级不到当前版本，或者升级后解码失败
+    Migrate(MigrateError),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::Io(e) => write!(f, "{e}"),
+            PersistError::Format { format, message } => write!(f, "{format:?}: {message}"),
+            PersistError::UnknownFormat(path) => {
+                write!(f, "unknown file format: {}", path.display())
+            }
+            PersistError::Migrate(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PersistError::Io(e) => Some(e),
+            PersistError::Migrate(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PersistError {
+    fn from(e: io::Error) -> Self {
+        PersistError::Io(e)
+    }
+}
+
+impl From<MigrateError> for PersistError {
+    fn from(e: MigrateError) -> Self {
+        PersistError::Migrate(e)
+    }
+}
+
+fn format_error(format: Format, e: impl fmt::Display) -> PersistError {
+    PersistError::Format {
+        format,
+        message: e.to_string(),
+    }
+}
+
+/// TOML 顶层只能是表
+#[derive(Serialize, Deserialize)]
+struct TomlFile<T> {
+    users: Vec<T>,
+}
+
+/// CSV 的表头，`version` 之后是 `User` 的字段
+pub(crate) const CSV_HEADER: &str = "version,status,username,email,sign_in_count,password,\
+                                     last_sign_in,failed_sign_ins,locked_until,activated_at,\
+                                     suspended_at,deleted_at";
+
+/// CSV 里这些列是整数或布尔值，其余是字符串；空着的格子都是没有值
+const CSV_NUMBERS: &[&str] = &[
+    "version",
+    "sign_in_count",
+    "last_sign_in",
+    "failed_sign_ins",
+    "locked_until",
+    "activated_at",
+    "suspended_at",
+    "deleted_at",
+];
+const CSV_BOOLS: &[&str] = &["active"];
+
+/// 把 `users` 编码成 `format` 格式的文本，每条记录装进当前版本的信封。
+pub fn to_string(users: &[User], format: Format) -> Result<String, PersistError> {
+    let envelopes: Vec<Envelope<&User>> = users
+        .iter()
+        .map(|user| Envelope {
+            version: CURRENT_VERSION,
+            record: user,
+        })
+        .collect();
+    match format {
+        Format::Json => {
+            serde_json::to_string_pretty(&envelopes).map_err(|e| format_error(format, e))
+        }
+        Format::Toml => {
+            toml::to_string(&TomlFile { users: envelopes }).map_err(|e| format_error(format, e))
+        }
+        Format::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(Vec::new());
+            let csv = |e: csv::Error| format_error(format, e);
+            writer.write_record(CSV_HEADER.split(',')).map_err(csv)?;
+            for user in users {
+                writer
+                    .write_field(CURRENT_VERSION.to_string())
+                    .map_err(csv)?;
+                writer.serialize(user).map_err(csv)?;
+            }
+            let bytes = writer.into_inner().map_err(|e| format_error(format, e))?;
+            String::from_utf8(bytes).map_err(|e| format_error(format, e))
+        }
+    }
+}
+
+/// 一格 CSV 按列名换成 JSON 值
+fn csv_value(column: &str, cell: &str) -> Result<Value, String> {
+    if cell.is_empty() {
+        return Ok(Value::Null);
+    }
+    if CSV_NUMBERS.contains(&column) {
+        let number: u64 = cell
+            .parse()
+            .map_err(|_| format!("column `{column}`: `{cell}` is not a number"))?;
+        return Ok(Value::from(number));
+    }
+    if CSV_BOOLS.contains(&column) {
+        let flag: bool = cell
+            .parse()
+            .map_err(|_| format!("column `{column}`: `{cell}` is not `true` or `false`"))?;
+        return Ok(Value::from(flag));
+    }
+    Ok(Value::from(cell))
+}
+
+/// 读出 `format` 格式的文本里的记录，还没有拆信封，也没有升级。
+pub(crate) fn records(text: &str, format: Format) -> Result<Vec<Value>, PersistError> {
+    match format {
+        Format::Json => serde_json::from_str(text).map_err(|e| format_error(format, e)),
+        Format::Toml => toml::from_str::<TomlFile<Value>>(text)
+            .map(|file| file.users)
+            .map_err(|e| format_error(format, e)),
+        Format::Csv => {
+            let mut reader = csv::Reader::from_reader(text.as_bytes());
+            let header = reader
+                .headers()
+                .map_err(|e| format_error(format, e))?
+                .clone();
+            let mut records = Vec::new();
+            for row in reader.records() {
+                let row = row.map_err(|e| format_error(format, e))?;
+                let mut fields: Map<String, Value> = header
+                    .iter()
+                    .zip(&row)
+                    .map(|(column, cell)| Ok((column.to_string(), csv_value(column, cell)?)))
+                    .collect::<Result<_, String>>()
+                    .map_err(|e| format_error(format, e))?;
+                // 有版本号的行就是拆开的信封
+                let record = match fields.remove("version") {
+                    Some(version) if !version.is_null() => serde_json::json!({
+                        "version": version,
+                        "record": fields,
+                    }),
+                    _ => Value::Object(fields),
+                };
+                records.push(record);
+            }
+            Ok(records)
+        }
+    }
+}
+
+/// 从 `format` 格式的文本解码出用户，旧版本的记录先升级到当前版本。
+pub fn from_str(text: &str, format: Format) -> Result<Vec<User>, PersistError> {
+    migrate::load(text, format).map(|report| report.users)
+}
+
+pub(crate) fn format_of(path: &Path) -> Result<Format, PersistError> {
+    Format::from_path(path).ok_or_else(|| PersistError::UnknownFormat(path.to_path_buf()))
+}
+
+/// 按扩展名选格式，读出文件里的所有用户。
+pub fn load(path: &Path) -> Result<Vec<User>, PersistError> {
+    let format = format_of(path)?;
+    from_str(&fs::read_to_string(path)?, format)
+}
+
+/// 按扩展名选格式，原子地把 `users` 写进 `path`。
+pub fn save(path: &Path, users: &[User]) -> Result<(), PersistError> {
+    let text = to_string(users, format_of(path)?)?;
+    write_atomic(path, text.as_bytes())?;
+    Ok(())
+}
+
+/// 本进程里下一个临时文件的序号
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// 写到同目录的临时文件再改名，同一个文件系统里改名是原子的。
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| {
+        let mut file = fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+    sync_dir(dir)
+}
+
+/// 把目录项的改动（这里是改名）刷到磁盘。
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    fs::File::open(dir)?.sync_all()
+}
+
+/// Windows 上目录不能当文件打开，改名由文件系统自己保证落盘。
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,194 @@
+//! 账户的唯一来源
+//!
+//! [`UserRegistry`] 拥有所有的 [`User`]，用户名和邮箱都不能重复；邮箱不区分大小写，
+//! `Alice@Example.com` 和 `alice@example.com` 算同一个。用户名也不能和已有的名字看起来一样
+//! （见 [`UsernamePolicy::confusable_with`](crate::UsernamePolicy::confusable_with)），免得有人注册一个
+//! 冒充别人的账户；为此按用户名的骨架也建了索引，检查不用把所有名字都比一遍。
+//! 每个账户有一个不随改名变化的 [`UserId`]，和 SQLite 的 `AUTOINCREMENT` 一样从 1 开始，删掉的编号不再用。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::email::Email;
+use crate::user::User;
+
+/// 账户编号，创建时分配，之后不再改变；序列化成数字
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UserId(pub u64);
+
+impl fmt::Display for UserId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// 操作失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// 用户名已经被别的账户占用
+    DuplicateUsername(String),
+    /// 用户名和别的账户的 `existing` 看起来一样，例如用西里尔字母 `а` 拼出来的 `аlice`
+    ConfusableUsername {
+        username: String,
+        existing: String,
+    },
+    /// 邮箱已经被别的账户占用（不区分大小写）
+    DuplicateEmail(String),
+    NotFound(UserId),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::DuplicateUsername(name) => write!(f, "username `{name}` is taken"),
+            RegistryError::ConfusableUsername { username, existing } => {
+                write!(
+                    f,
+                    "username `{username}` looks like the existing `{existing}`"
+                )
+            }
+            RegistryError::DuplicateEmail(email) => write!(f, "email `{email}` is taken"),
+            RegistryError::NotFound(id) => write!(f, "no user {id}"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// 域名已经规范化过，本地部分再统一成小写
+pub(crate) fn email_key(email: &Email) -> String {
+    format!("{}@{}", email.local_part().to_lowercase(), email.domain())
+}
+
+/// 所有账户，按用户名、用户名的骨架和邮箱建了索引
+#[derive(Debug, Clone, Default)]
+pub struct UserRegistry {
+    users: BTreeMap<UserId, User>,
+    by_username: HashMap<String, UserId>,
+    by_skeleton: HashMap<String, UserId>,
+    by_email: HashMap<String, UserId>,
+    /// 最近分配的编号，还没分配过时是 0
+    last_id: u64,
+}
+
+impl UserRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 用一批用户建注册表，编号按顺序从 1 分配；有重复的用户名或邮箱时报错。
+    pub fn from_users(users: impl IntoIterator<Item = User>) -> Result<Self, RegistryError> {
+        let mut registry = Self::new();
+        for user in users {
+            registry.create(user)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    /// 按编号顺序遍历所有账户。
+    pub fn iter(&self) -> impl Iterator<Item = (UserId, &User)> {
+        self.users.iter().map(|(id, user)| (*id, user))
+    }
+
+    /// 加入一个新账户。用户名或邮箱被占用时拒绝。
+    pub fn create(&mut self, user: User) -> Result<UserId, RegistryError> {
+        self.check_unique(&user, None)?;
+        self.last_id += 1;
+        let id = UserId(self.last_id);
+        self.index(id, &user);
+        self.users.insert(id, user);
+        Ok(id)
+    }
+
+    pub fn get(&self, id: UserId) -> Option<&User> {
+        self.users.get(&id)
+    }
+
+    pub fn find_by_username(&self, username: &str) -> Option<(UserId, &User)> {
+        let id = *self.by_username.get(username)?;
+        Some((id, &self.users[&id]))
+    }
+
+    /// 按邮箱查找，不区分大小写；国际化域名写成 Unicode 或 Punycode 都能找到。
+    pub fn find_by_email(&self, email: &str) -> Option<(UserId, &User)> {
+        let email = Email::parse(email).ok()?;
+        let id = *self.by_email.get(&email_key(&email))?;
+        Some((id, &self.users[&id]))
+    }
+
+    /// 修改一个账户。`change` 在副本上修改，改完的用户名或邮箱和别的账户冲突时原账户保持不变。
+    pub fn update(
+        &mut self,
+        id: UserId,
+        change: impl FnOnce(&mut User),
+    ) -> Result<&User, RegistryError> {
+        let mut user = self.get(id).ok_or(RegistryError::NotFound(id))?.clone();
+        change(&mut user);
+        self.check_unique(&user, Some(id))?;
+        self.replace(id, user);
+        Ok(&self.users[&id])
+    }
+
+    /// 删除一个账户，把它交还给调用方。
+    pub fn remove(&mut self, id: UserId) -> Result<User, RegistryError> {
+        let user = self.users.remove(&id).ok_or(RegistryError::NotFound(id))?;
+        self.unindex(&user);
+        Ok(user)
+    }
+
+    /// 换掉 `id` 对应的账户并更新索引，唯一性由调用方保证。
+    fn replace(&mut self, id: UserId, user: User) {
+        if let Some(old) = self.users.remove(&id) {
+            self.unindex(&old);
+        }
+        self.index(id, &user);
+        self.users.insert(id, user);
+    }
+
+    /// 用户名和邮箱有没有被 `except` 以外的账户占用，用户名是否和它们的看起来一样；
+    /// 改的是 `except` 而且用户名没变时不查用户名
+    fn check_unique(&self, user: &User, except: Option<UserId>) -> Result<(), RegistryError> {
+        let taken = |id: Option<&UserId>| id.is_some_and(|id| Some(*id) != except);
+        let renamed = except.is_none_or(|id| self.users[&id].username != user.username);
+        if renamed {
+            if taken(self.by_username.get(user.username.as_str())) {
+                return Err(RegistryError::DuplicateUsername(user.username.to_string()));
+            }
+            if let Some(id) = self.by_skeleton.get(&user.username.skeleton()) {
+                if Some(*id) != except {
+                    return Err(RegistryError::ConfusableUsername {
+                        username: user.username.to_string(),
+                        existing: self.users[id].username.to_string(),
+                    });
+                }
+            }
+        }
+        if taken(self.by_email.get(&email_key(&user.email))) {
+            return Err(RegistryError::DuplicateEmail(user.email.to_string()));
+        }
+        Ok(())
+    }
+
+    fn index(&mut self, id: UserId, user: &User) {
+        self.by_username.insert(user.username.to_string(), id);
+        self.by_skeleton.insert(user.username.skeleton(), id);
+        self.by_email.insert(email_key(&user.email), id);
+    }
+
+    fn unindex(&mut self, user: &User) {
+        self.by_username.remove(user.username.as_str());
+        self.by_skeleton.remove(&user.username.skeleton());
+        self.by_email.remove(&email_key(&user.email));
+    }
+}
@@ -0,0 +1,222 @@
+//! 会话
+//!
+//! 登录成功后给客户端发一个随机的不透明令牌，之后的请求拿令牌来换回是哪个账户。每个会话有两种过期：
+//! 滑动过期——闲置超过 `idle` 就失效，每次使用都会往后推；绝对过期——从创建起最多活 `absolute`，
+//! 不管用得多勤。默认的最长寿命就是 concepts.rs 里那个 `THREE_HOURS_IN_SECONDS`。
+//!
+//! 存下来的只是令牌的 SHA-256，会话表泄露了也拿不到能用的令牌。只给存在且 `Active` 的账户开会话；
+//! 每次使用都会查一遍账户状态，账户停用或注销之后它的会话在下次使用时作废。
+//!
+//! 所有时间都从注入的 [`Clock`] 取，测试时可以用 [`ManualClock`](crate::ManualClock) 拨表。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use sha2::{Digest, Sha256};
+
+use crate::clock::{later, Clock};
+use crate::registry::{UserId, UserRegistry};
+use crate::user::User;
+
+/// 会话默认的最长寿命，和 concepts.rs 讲常量时声明的一样
+pub const THREE_HOURS_IN_SECONDS: u32 = 60 * 60 * 3;
+
+/// 令牌的随机字节数
+const TOKEN_BYTES: usize = 32;
+
+/// 会话操作失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    /// 没有这个令牌，或者已经被撤销
+    NotFound,
+    /// 在 `at` 时过期了
+    Expired { at: SystemTime },
+    /// 没有这个账户
+    UnknownUser(UserId),
+    /// 账户不是 `Active` 状态或者已经删除
+    Inactive,
+    /// 系统随机数不可用
+    Rng(String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::NotFound => write!(f, "no such session"),
+            SessionError::Expired { .. } => write!(f, "session has expired"),
+            SessionError::UnknownUser(id) => write!(f, "no user {id}"),
+            SessionError::Inactive => write!(f, "account is inactive"),
+            SessionError::Rng(e) => write!(f, "failed to generate session token: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+/// 会话令牌：32 个随机字节的十六进制形式，除了能查到会话以外不带任何信息
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Token(String);
+
+impl Token {
+    fn generate() -> Result<Self, SessionError> {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        getrandom::fill(&mut bytes).map_err(|e| SessionError::Rng(e.to_string()))?;
+        Ok(Token(bytes.iter().map(|b| format!("{b:02x}")).collect()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// 会话表的键：令牌的 SHA-256
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct TokenHash([u8; 32]);
+
+impl TokenHash {
+    fn of(token: &str) -> Self {
+        TokenHash(Sha256::digest(token.as_bytes()).into())
+    }
+}
+
+/// 令牌等同于密码，调试输出里只留开头几位
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Token({}...)", &self.0[..6])
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 一个登录会话
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub user: UserId,
+    pub created: SystemTime,
+    /// 最近一次使用的时间
+    pub last_seen: SystemTime,
+    /// 按当前的 `last_seen` 算出的过期时间
+    pub expires: SystemTime,
+}
+
+/// 会话的寿命
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionPolicy {
+    /// 闲置多久失效（滑动过期）
+    pub idle: Duration,
+    /// 创建后最多多久失效（绝对过期）
+    pub absolute: Duration,
+}
+
+impl Default for SessionPolicy {
+    /// 闲置 30 分钟或者创建满三小时就失效。
+    fn default() -> Self {
+        SessionPolicy {
+            idle: Duration::from_secs(30 * 60),
+            absolute: Duration::from_secs(THREE_HOURS_IN_SECONDS.into()),
+        }
+    }
+}
+
+impl SessionPolicy {
+    fn expires(&self, created: SystemTime, last_seen: SystemTime) -> SystemTime {
+        later(last_seen, self.idle).min(later(created, self.absolute))
+    }
+}
+
+/// 所有有效的会话
+#[derive(Debug)]
+pub struct SessionStore<C> {
+    sessions: HashMap<TokenHash, Session>,
+    policy: SessionPolicy,
+    clock: C,
+}
+
+impl<C: Clock> SessionStore<C> {
+    pub fn new(policy: SessionPolicy, clock: C) -> Self {
+        SessionStore {
+            sessions: HashMap::new(),
+            policy,
+            clock,
+        }
+    }
+
+    /// 为 `user` 开一个新会话，账户必须在 `registry` 里并且是 `Active` 状态。
+    pub fn issue(&mut self, registry: &UserRegistry, user: UserId) -> Result<Token, SessionError> {
+        if !registry
+            .get(user)
+            .ok_or(SessionError::UnknownUser(user))?
+            .is_active()
+        {
+            return Err(SessionError::Inactive);
+        }
+        let token = Token::generate()?;
+        let now = self.clock.now();
+        let session = Session {
+            user,
+            created: now,
+            last_seen: now,
+            expires: self.policy.expires(now, now),
+        };
+        self.sessions.insert(TokenHash::of(token.as_str()), session);
+        Ok(token)
+    }
+
+    /// 查出令牌对应的会话并记一次使用，滑动过期时间随之后推。过期的会话，以及账户在 `registry`
+    /// 里已经不是 `Active` 状态的会话，顺手删掉。
+    pub fn validate(
+        &mut self,
+        registry: &UserRegistry,
+        token: &str,
+    ) -> Result<&Session, SessionError> {
+        let now = self.clock.now();
+        let key = TokenHash::of(token);
+        let session = self.sessions.get(&key).ok_or(SessionError::NotFound)?;
+        if now >= session.expires {
+            let at = session.expires;
+            self.sessions.remove(&key);
+            return Err(SessionError::Expired { at });
+        }
+        if !registry.get(session.user).is_some_and(User::is_active) {
+            self.sessions.remove(&key);
+            return Err(SessionError::Inactive);
+        }
+        let session = self.sessions.get_mut(&key).expect("checked above");
+        session.last_seen = now;
+        session.expires = self.policy.expires(session.created, now);
+        Ok(session)
+    }
+
+    /// 撤销一个会话（退出登录）。令牌不存在时返回 `false`。
+    pub fn revoke(&mut self, token: &str) -> bool {
+        self.sessions.remove(&TokenHash::of(token)).is_some()
+    }
+
+    /// 撤销 `user` 的所有会话（在所有设备上退出），返回撤销了几个。
+    pub fn revoke_all(&mut self, user: UserId) -> usize {
+        let before = self.sessions.len();
+        self.sessions.retain(|_, session| session.user != user);
+        before - self.sessions.len()
+    }
+
+    /// `user` 还没过期的会话
+    pub fn sessions_for(&self, user: UserId) -> impl Iterator<Item = &Session> {
+        let now = self.clock.now();
+        self.sessions
+            .values()
+            .filter(move |session| session.user == user && now < session.expires)
+    }
+
+    /// 删掉所有已经过期的会话，返回删了几个。
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let before = self.sessions.len();
+        self.sessions.retain(|_, session| now < session.expires);
+        before - self.sessions.len()
+    }
+}
@@ -0,0 +1,166 @@
+//! 登录
+//!
+//! [`Authenticator`] 把登录需要的几件事串起来：只有 `Active` 状态的账户能登录；密码正确时
+//! `sign_in_count` 加一、记下登录时间、清掉失败记录；密码错误时累计连续失败次数，每次失败后要等一段翻倍增长的时间才能再试，
+//! 连续失败达到阈值就锁定一段时间，用来挡住暴力破解。
+//!
+//! 锁定期过了之后失败次数清零，重新开始计算。
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::clock::{later, Clock};
+use crate::password::{Kdf, PasswordError};
+use crate::registry::{UserId, UserRegistry};
+use crate::user::User;
+
+/// 登录被拒绝的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignInError {
+    /// 用户名或密码不对；不区分是哪一个，免得泄露账户是否存在
+    InvalidCredentials,
+    /// 账户不是 `Active` 状态：未验证、已停用或已注销
+    Inactive,
+    /// 上次失败后的退避时间还没过
+    Throttled { retry_after: Duration },
+    /// 连续失败太多次，锁定到 `until`
+    LockedOut { until: SystemTime },
+    /// 凭据本身有问题，例如没设置密码或者哈希损坏
+    Password(PasswordError),
+}
+
+impl fmt::Display for SignInError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignInError::InvalidCredentials => write!(f, "invalid username or password"),
+            SignInError::Inactive => write!(f, "account is inactive"),
+            SignInError::Throttled { retry_after } => {
+                write!(
+                    f,
+                    "too many attempts, retry in {}s",
+                    retry_after.as_secs_f64().ceil()
+                )
+            }
+            SignInError::LockedOut { until } => {
+                let secs = until
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                write!(f, "account is locked until {secs} (unix time)")
+            }
+            SignInError::Password(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for SignInError {}
+
+/// 退避和锁定的参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignInPolicy {
+    /// 连续失败多少次后锁定
+    pub lockout_threshold: u32,
+    /// 第一次失败后的等待时间，之后每次翻倍
+    pub base_delay: Duration,
+    /// 退避时间的上限
+    pub max_delay: Duration,
+    /// 锁定多久
+    pub lockout: Duration,
+}
+
+impl Default for SignInPolicy {
+    /// 失败后等 1 秒、2 秒、4 秒……最多 30 秒，连续 5 次失败锁定 15 分钟。
+    fn default() -> Self {
+        SignInPolicy {
+            lockout_threshold: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            lockout: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl SignInPolicy {
+    /// 连续失败 `failures` 次之后要等多久
+    pub fn delay(&self, failures: u32) -> Duration {
+        if failures >= self.lockout_threshold {
+            return self.lockout;
+        }
+        let factor = 1u32
+            .checked_shl(failures.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+}
+
+/// 登录入口
+#[derive(Debug, Clone)]
+pub struct Authenticator<C> {
+    pub kdf: Kdf,
+    pub policy: SignInPolicy,
+    pub clock: C,
+}
+
+impl<C: Clock> Authenticator<C> {
+    pub fn new(kdf: Kdf, policy: SignInPolicy, clock: C) -> Self {
+        Authenticator { kdf, policy, clock }
+    }
+
+    /// 用密码登录 `user`，成功和失败都会更新它的登录记录。
+    pub fn sign_in(&self, user: &mut User, password: &str) -> Result<(), SignInError> {
+        if !user.is_active() {
+            return Err(SignInError::Inactive);
+        }
+        let now = self.clock.now();
+        if let Some(until) = user.locked_until {
+            let locked = user.failed_sign_ins >= self.policy.lockout_threshold;
+            match until.duration_since(now) {
+                Ok(left) if !left.is_zero() => {
+                    return Err(if locked {
+                        SignInError::LockedOut { until }
+                    } else {
+                        SignInError::Throttled { retry_after: left }
+                    });
+                }
+                _ if locked => user.failed_sign_ins = 0,
+                _ => {}
+            }
+        }
+
+        match user.authenticate(&self.kdf, password) {
+            Ok(()) => {
+                user.last_sign_in = Some(now);
+                user.failed_sign_ins = 0;
+                user.locked_until = None;
+                Ok(())
+            }
+            Err(PasswordError::Mismatch) => {
+                user.failed_sign_ins += 1;
+                user.locked_until = Some(later(now, self.policy.delay(user.failed_sign_ins)));
+                Err(SignInError::InvalidCredentials)
+            }
+            Err(e) => Err(SignInError::Password(e)),
+        }
+    }
+
+    /// 按用户名找到账户再登录，登录记录写回注册表。账户不存在时也照样算一遍哈希，
+    /// 和密码错误花的时间一样。
+    pub fn sign_in_username(
+        &self,
+        registry: &mut UserRegistry,
+        username: &str,
+        password: &str,
+    ) -> Result<UserId, SignInError> {
+        let Some((id, _)) = registry.find_by_username(username) else {
+            self.kdf.verify_dummy(password);
+            return Err(SignInError::InvalidCredentials);
+        };
+        let mut result = Ok(());
+        registry
+            .update(id, |user| result = self.sign_in(user, password))
+            .map_err(|_| SignInError::InvalidCredentials)?;
+        result.map(|()| id)
+    }
+}
@@ -0,0 +1,396 @@
+//! SQLite 存储
+//!
+//! [`SqliteStorage`] 把账户存进一个 SQLite 文件，不需要单独的数据库服务。打开时建表和索引：
+//! `username` 上的唯一索引保证用户名不重复；邮箱存两列，`email` 是原样的地址，`email_key` 是
+//! 本地部分转小写后的形式，唯一索引建在 `email_key` 上，所以和 [`UserRegistry`](crate::UserRegistry)
+//! 一样不区分大小写。用户名的骨架存在 `skeleton` 列，上面的唯一索引拦下看起来一样的名字，
+//! 检查时不用把所有名字读出来比一遍。`PRAGMA user_version` 记录表结构的版本 [`SCHEMA_VERSION`]，打开旧版本的
+//! 文件时先把表一步步升级到当前的形状；比代码还新的文件拒绝打开，免得把它改坏。
+//!
+//! 编号用 `AUTOINCREMENT` 分配，和 [`UserRegistry`](crate::UserRegistry) 一样从 1 开始，删掉的编号
+//! 不会再用。事务用 `SAVEPOINT` 实现，所以可以嵌套；`f` panic 时也会回滚。
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Row};
+
+use crate::email::Email;
+use crate::password::Credential;
+use crate::registry::{email_key, RegistryError, UserId};
+use crate::storage::{Storage, StorageError};
+use crate::user::User;
+use crate::username::Username;
+
+/// 表结构的版本，和 [`migrate::CURRENT_VERSION`](crate::migrate::CURRENT_VERSION) 各自增长。
+/// 版本 3 的表有 `active` 列，版本 4 换成了 `status`，版本 5 加了 `skeleton`。
+pub const SCHEMA_VERSION: u32 = 5;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS users (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    status          TEXT    NOT NULL,
+    username        TEXT    NOT NULL,
+    skeleton        TEXT    NOT NULL,
+    email           TEXT    NOT NULL,
+    email_key       TEXT    NOT NULL,
+    sign_in_count   INTEGER NOT NULL,
+    password        TEXT,
+    last_sign_in    INTEGER,
+    failed_sign_ins INTEGER NOT NULL,
+    locked_until    INTEGER,
+    activated_at    INTEGER,
+    suspended_at    INTEGER,
+    deleted_at      INTEGER
+);
+CREATE UNIQUE INDEX IF NOT EXISTS users_username ON users (username);
+CREATE UNIQUE INDEX IF NOT EXISTS users_skeleton ON users (skeleton);
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_key ON users (email_key);
+";
+
+/// 从 `from` 版本升到下一个版本的 SQL，按版本顺序排列
+const UPGRADES: &[(u32, &str)] = &[(3, UPGRADE_FROM_3), (4, UPGRADE_FROM_4)];
+
+/// `active` 列换成 `status` 和三个转移时间，见 [`migrate`](crate::migrate)
+const UPGRADE_FROM_3: &str = "
+ALTER TABLE users ADD COLUMN status TEXT NOT NULL DEFAULT 'active';
+UPDATE users SET status = 'suspended: deactivated' WHERE active = 0;
+ALTER TABLE users DROP COLUMN active;
+ALTER TABLE users ADD COLUMN activated_at INTEGER;
+ALTER TABLE users ADD COLUMN suspended_at INTEGER;
+ALTER TABLE users ADD COLUMN deleted_at INTEGER;
+";
+
+/// 加上 `skeleton` 列；SQLite 算不了骨架，升级时由 [`fill_skeletons`] 补上
+const UPGRADE_FROM_4: &str = "
+ALTER TABLE users ADD COLUMN skeleton TEXT;
+";
+
+const COLUMNS: &str = "id, status, username, email, sign_in_count, password, last_sign_in, \
+                       failed_sign_ins, locked_until, activated_at, suspended_at, deleted_at";
+
+fn backend(e: impl ToString) -> StorageError {
+    StorageError::Backend(e.to_string())
+}
+
+/// 给升级上来、还没有骨架的行算出骨架；已有的名字里有看起来一样的就没法建唯一索引，报错
+fn fill_skeletons(conn: &Connection) -> Result<(), StorageError> {
+    let mut statement = conn
+        .prepare("SELECT id, username FROM users WHERE skeleton IS NULL")
+        .map_err(backend)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(backend)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(backend)?;
+    let mut seen = HashMap::new();
+    for (id, username) in rows {
+        let skeleton = Username::from_stored(username.as_str()).skeleton();
+        if let Some(existing) = seen.insert(skeleton.clone(), username.clone()) {
+            return Err(backend(format!(
+                "usernames `{existing}` and `{username}` look alike, rename one before upgrading"
+            )));
+        }
+        conn.execute(
+            "UPDATE users SET skeleton = ?2 WHERE id = ?1",
+            params![id, skeleton],
+        )
+        .map_err(backend)?;
+    }
+    Ok(())
+}
+
+fn to_secs(time: Option<SystemTime>) -> Option<i64> {
+    time.map(|time| {
+        let secs = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        i64::try_from(secs).unwrap_or(i64::MAX)
+    })
+}
+
+fn from_secs(secs: Option<i64>) -> Option<SystemTime> {
+    secs.map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64))
+}
+
+/// 一行换回一个用户；格式不对的值（数据库被改坏）报错而不是造出不合法的用户，
+/// 用户名按起名时的策略检查过，原样接受
+fn read_row(row: &Row) -> rusqlite::Result<Result<(UserId, User), StorageError>> {
+    let id: i64 = row.get("id")?;
+    let username: String = row.get("username")?;
+    let email: String = row.get("email")?;
+    let password: Option<String> = row.get("password")?;
+    let sign_in_count: i64 = row.get("sign_in_count")?;
+    let failed_sign_ins: i64 = row.get("failed_sign_ins")?;
+    let status: String = row.get("status")?;
+    let last_sign_in: Option<i64> = row.get("last_sign_in")?;
+    let locked_until: Option<i64> = row.get("locked_until")?;
+    let activated_at: Option<i64> = row.get("activated_at")?;
+    let suspended_at: Option<i64> = row.get("suspended_at")?;
+    let deleted_at: Option<i64> = row.get("deleted_at")?;
+    Ok((|| {
+        let user = User {
+            status: status.parse().map_err(backend)?,
+            username: Username::from_stored(username),
+            email: email.parse().map_err(backend)?,
+            sign_in_count: sign_in_count.try_into().map_err(backend)?,
+            password: password
+                .map(Credential::try_from)
+                .transpose()
+                .map_err(backend)?,
+            last_sign_in: from_secs(last_sign_in),
+            failed_sign_ins: failed_sign_ins.try_into().map_err(backend)?,
+            locked_until: from_secs(locked_until),
+            activated_at: from_secs(activated_at),
+            suspended_at: from_secs(suspended_at),
+            deleted_at: from_secs(deleted_at),
+        };
+        Ok((UserId(id as u64), user))
+    })())
+}
+
+/// 存在 SQLite 数据库里的账户
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    /// 打开（不存在就创建）一个数据库文件，建好表和索引。
+    pub fn open(path: &Path) -> Result<Self, StorageError> {
+        Self::init(Connection::open(path).map_err(backend)?)
+    }
+
+    /// 只在内存里的数据库，连接关掉就没了。
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Self::init(Connection::open_in_memory().map_err(backend)?)
+    }
+
+    /// 新文件直接建表；旧版本的文件沿着 [`UPGRADES`] 升级，整个过程在一个事务里。
+    fn init(mut conn: Connection) -> Result<Self, StorageError> {
+        let version: u32 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(backend)?;
+        if version > SCHEMA_VERSION {
+            return Err(backend(format!(
+                "database schema version {version} is newer than {SCHEMA_VERSION}"
+            )));
+        }
+        let tx = conn.transaction().map_err(backend)?;
+        if version != 0 {
+            for from in version..SCHEMA_VERSION {
+                let (_, upgrade) = UPGRADES.iter().find(|(v, _)| *v == from).ok_or_else(|| {
+                    backend(format!("cannot upgrade database schema version {from}"))
+                })?;
+                tx.execute_batch(upgrade).map_err(backend)?;
+            }
+            if version < 5 {
+                fill_skeletons(&tx)?;
+            }
+        }
+        tx.execute_batch(SCHEMA).map_err(backend)?;
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION)
+            .map_err(backend)?;
+        tx.commit().map_err(backend)?;
+        Ok(SqliteStorage { conn })
+    }
+
+    /// 唯一索引冲突换成和注册表一样的错误，其余的原样报告
+    fn write_error(&self, e: rusqlite::Error, user: &User) -> StorageError {
+        if let rusqlite::Error::SqliteFailure(failure, Some(message)) = &e {
+            if failure.code == ErrorCode::ConstraintViolation {
+                if message.contains("users.username") {
+                    return RegistryError::DuplicateUsername(user.username.to_string()).into();
+                }
+                if message.contains("users.skeleton") {
+                    return self.confusable(user);
+                }
+                if message.contains("users.email_key") {
+                    return RegistryError::DuplicateEmail(user.email.to_string()).into();
+                }
+            }
+        }
+        backend(e)
+    }
+
+    /// `user` 的骨架被占了：名字完全一样算重名，否则是看起来一样
+    fn confusable(&self, user: &User) -> StorageError {
+        let existing = self.conn.query_row(
+            "SELECT username FROM users WHERE skeleton = ?1",
+            [user.username.skeleton()],
+            |row| row.get::<_, String>(0),
+        );
+        match existing {
+            Ok(existing) if existing == user.username.as_str() => {
+                RegistryError::DuplicateUsername(existing).into()
+            }
+            Ok(existing) => RegistryError::ConfusableUsername {
+                username: user.username.to_string(),
+                existing,
+            }
+            .into(),
+            Err(e) => backend(e),
+        }
+    }
+
+    fn query_one(
+        &self,
+        filter: &str,
+        value: impl rusqlite::ToSql,
+    ) -> Result<Option<(UserId, User)>, StorageError> {
+        self.conn
+            .query_row(
+                &format!("SELECT {COLUMNS} FROM users WHERE {filter} = ?1"),
+                [value],
+                read_row,
+            )
+            .optional()
+            .map_err(backend)?
+            .transpose()
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn insert(&mut self, user: &User) -> Result<UserId, StorageError> {
+        self.conn
+            .execute(
+                "INSERT INTO users (status, username, skeleton, email, email_key, sign_in_count,
+                                    password, last_sign_in, failed_sign_ins, locked_until,
+                                    activated_at, suspended_at, deleted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    user.status.to_string(),
+                    user.username.as_str(),
+                    user.username.skeleton(),
+                    user.email.as_str(),
+                    email_key(&user.email),
+                    i64::try_from(user.sign_in_count).map_err(backend)?,
+                    user.password.as_ref().map(|c| c.as_phc()),
+                    to_secs(user.last_sign_in),
+                    user.failed_sign_ins,
+                    to_secs(user.locked_until),
+                    to_secs(user.activated_at),
+                    to_secs(user.suspended_at),
+                    to_secs(user.deleted_at),
+                ],
+            )
+            .map_err(|e| self.write_error(e, user))?;
+        Ok(UserId(self.conn.last_insert_rowid() as u64))
+    }
+
+    fn get(&self, id: UserId) -> Result<Option<User>, StorageError> {
+        Ok(self.query_one("id", id.0 as i64)?.map(|(_, user)| user))
+    }
+
+    fn find_by_username(&self, username: &str) -> Result<Option<(UserId, User)>, StorageError> {
+        self.query_one("username", username)
+    }
+
+    fn find_by_email(&self, email: &Email) -> Result<Option<(UserId, User)>, StorageError> {
+        self.query_one("email_key", email_key(email))
+    }
+
+    fn update(&mut self, id: UserId, user: &User) -> Result<(), StorageError> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE users SET status = ?2, username = ?3, skeleton = ?4, email = ?5,
+                                  email_key = ?6, sign_in_count = ?7, password = ?8,
+                                  last_sign_in = ?9, failed_sign_ins = ?10, locked_until = ?11,
+                                  activated_at = ?12, suspended_at = ?13, deleted_at = ?14
+                 WHERE id = ?1",
+                params![
+                    id.0 as i64,
+                    user.status.to_string(),
+                    user.username.as_str(),
+                    user.username.skeleton(),
+                    user.email.as_str(),
+                    email_key(&user.email),
+                    i64::try_from(user.sign_in_count).map_err(backend)?,
+                    user.password.as_ref().map(|c| c.as_phc()),
+                    to_secs(user.last_sign_in),
+                    user.failed_sign_ins,
+                    to_secs(user.locked_until),
+                    to_secs(user.activated_at),
+                    to_secs(user.suspended_at),
+                    to_secs(user.deleted_at),
+                ],
+            )
+            .map_err(|e| self.write_error(e, user))?;
+        if changed == 0 {
+            return Err(RegistryError::NotFound(id).into());
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, id: UserId) -> Result<User, StorageError> {
+        self.transaction(|storage| {
+            let user = storage.get(id)?.ok_or(RegistryError::NotFound(id))?;
+            storage
+                .conn
+                .execute("DELETE FROM users WHERE id = ?1", [id.0 as i64])
+                .map_err(backend)?;
+            Ok(user)
+        })
+    }
+
+    fn list(&self) -> Result<Vec<(UserId, User)>, StorageError> {
+        let mut statement = self
+            .conn
+            .prepare(&format!("SELECT {COLUMNS} FROM users ORDER BY id"))
+            .map_err(backend)?;
+        let rows = statement.query_map([], read_row).map_err(backend)?;
+        rows.map(|row| row.map_err(backend)?).collect()
+    }
+
+    fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, StorageError>,
+    ) -> Result<T, StorageError> {
+        let mut savepoint = Savepoint::begin(self)?;
+        let result = f(savepoint.storage);
+        match result {
+            Ok(_) => savepoint.end("RELEASE tx")?,
+            Err(_) => savepoint.end(ROLLBACK)?,
+        }
+        result
+    }
+}
+
+const ROLLBACK: &str = "ROLLBACK TO tx; RELEASE tx";
+
+/// 开着的 `SAVEPOINT`；没有正常结束就被丢掉（`f` panic 的时候）会回滚
+struct Savepoint<'a> {
+    storage: &'a mut SqliteStorage,
+    open: bool,
+}
+
+impl<'a> Savepoint<'a> {
+    fn begin(storage: &'a mut SqliteStorage) -> Result<Self, StorageError> {
+        storage
+            .conn
+            .execute_batch("SAVEPOINT tx")
+            .map_err(backend)?;
+        Ok(Savepoint {
+            storage,
+            open: true,
+        })
+    }
+
+    fn end(&mut self, sql: &str) -> Result<(), StorageError> {
+        self.open = false;
+        self.storage.conn.execute_batch(sql).map_err(backend)
+    }
+}
+
+impl Drop for Savepoint<'_> {
+    fn drop(&mut self) {
+        if self.open {
+            let _ = self.storage.conn.execute_batch(ROLLBACK);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! 账户状态
+//!
+//! `active: bool` 只分得清能不能登录，说不出“还没验证邮箱”“因为什么被封了”“已经注销”。
+//! [`Status`] 把账户的生命周期写成状态机，只允许这几种转移：
+//!
+//! ```text
+//! Pending ──activate──▶ Active ──suspend──▶ Suspended
+//!                         ▲                    │
+//!                         └──────activate──────┘
+//! 任何状态（除了 Deleted）──delete──▶ Deleted
+//! ```
+//!
+//! 其余的转移都返回 [`TransitionError`]，状态不变。每次转移都在 `User` 上记下进入新状态的时间
+//! （`activated_at`、`suspended_at`、`deleted_at`）；完整的转移历史见 [`audit`](crate::audit)。
+//!
+//! 状态序列化成字符串：`pending`、`active`、`suspended: <原因>`、`deleted`，CSV 里也能放进一列。
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::user::User;
+
+/// 账户所处的阶段
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Status {
+    /// 刚注册，等着验证邮箱
+    Pending,
+    Active,
+    /// 被管理员或者风控停用，`reason` 给人看
+    Suspended {
+        reason: String,
+    },
+    /// 已注销，不能再转到别的状态
+    Deleted,
+}
+
+impl Status {
+    /// 不带原因的名字，用在错误信息里
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Pending => "pending",
+            Status::Active => "active",
+            Status::Suspended { .. } => "suspended",
+            Status::Deleted => "deleted",
+        }
+    }
+
+    /// 能不能从 `self` 转到 `to`
+    pub fn can_transition_to(&self, to: &Status) -> bool {
+        matches!(
+            (self, to),
+            (Status::Pending, Status::Active)
+                | (Status::Active, Status::Suspended { .. })
+                | (Status::Suspended { .. }, Status::Active)
+                | (
+                    Status::Pending | Status::Active | Status::Suspended { .. },
+                    Status::Deleted
+                )
+        )
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Suspended { reason } => write!(f, "suspended: {reason}"),
+            status => f.write_str(status.name()),
+        }
+    }
+}
+
+/// 不认识的状态字符串
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownStatus(pub String);
+
+impl fmt::Display for UnknownStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown account status `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownStatus {}
+
+impl FromStr for Status {
+    type Err = UnknownStatus;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Status::Pending),
+            "active" => Ok(Status::Active),
+            "deleted" => Ok(Status::Deleted),
+            // 只去掉 `Display` 写的那一个空格，原因本身开头的空白要保留
+            _ => match s.strip_prefix("suspended: ") {
+                Some(reason) => Ok(Status::Suspended {
+                    reason: reason.to_string(),
+                }),
+                None => Err(UnknownStatus(s.to_string())),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for Status {
+    type Error = UnknownStatus;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Status> for String {
+    fn from(status: Status) -> Self {
+        status.to_string()
+    }
+}
+
+/// 不允许的状态转移
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitionError {
+    pub from: Status,
+    pub to: Status,
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot change account status from {} to {}",
+            self.from.name(),
+            self.to.name()
+        )
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+impl User {
+    /// 只有 `Active` 的账户能登录
+    pub fn is_active(&self) -> bool {
+        self.status == Status::Active
+    }
+
+    /// 转到 `to` 并记下时间；不允许的转移返回错误，账户不变。
+    pub fn transition(&mut self, to: Status, at: SystemTime) -> Result<(), TransitionError> {
+        if !self.status.can_transition_to(&to) {
+            return Err(TransitionError {
+                from: self.status.clone(),
+                to,
+            });
+        }
+        match to {
+            Status::Pending => {}
+            Status::Active => self.activated_at = Some(at),
+            Status::Suspended { .. } => self.suspended_at = Some(at),
+            Status::Deleted => self.deleted_at = Some(at),
+        }
+        self.status = to;
+        Ok(())
+    }
+
+    /// 验证邮箱后启用，或者解除停用。
+    pub fn activate(&mut self, at: SystemTime) -> Result<(), TransitionError> {
+        self.transition(Status::Active, at)
+    }
+
+    pub fn suspend(&mut self, reason: &str, at: SystemTime) -> Result<(), TransitionError> {
+        let reason = reason.to_string();
+        self.transition(Status::Suspended { reason }, at)
+    }
+
+    pub fn delete(&mut self, at: SystemTime) -> Result<(), TransitionError> {
+        self.transition(Status::Deleted, at)
+    }
+}
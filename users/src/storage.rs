@@ -0,0 +1,136 @@
+//! 账户存储
+//!
+//! [`Storage`] 把“存用户”抽象出来：增删改查、按用户名和邮箱查找、事务。唯一性约束和
+//! [`UserRegistry`] 一样——用户名区分大小写，邮箱不区分。实现有两个：[`MemoryStorage`] 包着一个
+//! `UserRegistry`，适合测试和小工具；[`SqliteStorage`](crate::sqlite::SqliteStorage) 存进
+//! SQLite 文件，见 [`sqlite`](crate::sqlite)。
+//!
+//! SQLite 里的行借不出 `&User`，所以所有读操作都返回拥有所有权的副本。
+
+use std::fmt;
+
+use crate::email::Email;
+use crate::registry::{RegistryError, UserId, UserRegistry};
+use crate::user::User;
+
+/// 存储操作失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    /// 违反了唯一性约束，或者账户不存在
+    Registry(RegistryError),
+    /// 后端自己的错误，例如 SQLite 打不开文件或者存的数据读不回来
+    Backend(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Registry(e) => write!(f, "{e}"),
+            StorageError::Backend(e) => write!(f, "storage error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<RegistryError> for StorageError {
+    fn from(e: RegistryError) -> Self {
+        StorageError::Registry(e)
+    }
+}
+
+/// 存放账户的地方
+pub trait Storage {
+    /// 存入一个新账户，分配编号。用户名或邮箱被占用时拒绝。
+    fn insert(&mut self, user: &User) -> Result<UserId, StorageError>;
+
+    fn get(&self, id: UserId) -> Result<Option<User>, StorageError>;
+
+    fn find_by_username(&self, username: &str) -> Result<Option<(UserId, User)>, StorageError>;
+
+    /// 按邮箱查找，不区分大小写。
+    fn find_by_email(&self, email: &Email) -> Result<Option<(UserId, User)>, StorageError>;
+
+    /// 用 `user` 换掉 `id` 对应的账户；冲突时原账户保持不变。
+    fn update(&mut self, id: UserId, user: &User) -> Result<(), StorageError>;
+
+    /// 删除一个账户，把它交还给调用方。
+    fn remove(&mut self, id: UserId) -> Result<User, StorageError>;
+
+    /// 按编号顺序列出所有账户。
+    fn list(&self) -> Result<Vec<(UserId, User)>, StorageError>;
+
+    /// 在事务里执行 `f`：`f` 返回 `Err` 时它做过的所有修改都撤销。事务可以嵌套。
+    fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, StorageError>,
+    ) -> Result<T, StorageError>
+    where
+        Self: Sized;
+}
+
+/// 存在内存里的账户，进程退出就没了
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    registry: UserRegistry,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn insert(&mut self, user: &User) -> Result<UserId, StorageError> {
+        Ok(self.registry.create(user.clone())?)
+    }
+
+    fn get(&self, id: UserId) -> Result<Option<User>, StorageError> {
+        Ok(self.registry.get(id).cloned())
+    }
+
+    fn find_by_username(&self, username: &str) -> Result<Option<(UserId, User)>, StorageError> {
+        Ok(self
+            .registry
+            .find_by_username(username)
+            .map(|(id, user)| (id, user.clone())))
+    }
+
+    fn find_by_email(&self, email: &Email) -> Result<Option<(UserId, User)>, StorageError> {
+        Ok(self
+            .registry
+            .find_by_email(email.as_str())
+            .map(|(id, user)| (id, user.clone())))
+    }
+
+    fn update(&mut self, id: UserId, user: &User) -> Result<(), StorageError> {
+        self.registry.update(id, |old| *old = user.clone())?;
+        Ok(())
+    }
+
+    fn remove(&mut self, id: UserId) -> Result<User, StorageError> {
+        Ok(self.registry.remove(id)?)
+    }
+
+    fn list(&self) -> Result<Vec<(UserId, User)>, StorageError> {
+        Ok(self
+            .registry
+            .iter()
+            .map(|(id, user)| (id, user.clone()))
+            .collect())
+    }
+
+    /// 先留一份快照，失败时整个换回去。
+    fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, StorageError>,
+    ) -> Result<T, StorageError> {
+        let snapshot = self.registry.clone();
+        let result = f(self);
+        if result.is_err() {
+            self.registry = snapshot;
+        }
+        result
+    }
+}
@@ -0,0 +1,80 @@
+//! 用户
+//!
+//! 和 struct.rs 里的定义一样：结构体拥有它所有的数据，所以字段都是自己拥有的类型而不是 `&str`。
+//! 邮箱是解析过的 [`Email`]，用户名是通过了 [`UsernamePolicy`](crate::UsernamePolicy) 的
+//! [`Username`]，不合法的值在构造 `User` 之前就被拒绝了。
+//!
+//! 密码只以 [`Credential`] 的形式保存；`sign_in_count` 只在 [`User::authenticate`] 验证通过时增加。
+//! 登录时间、连续失败次数和锁定时间由 [`Authenticator`](crate::Authenticator) 维护。
+//! 账户能不能用看 [`Status`]，状态只能通过 [`User::transition`] 按规定的路线改，见 [`status`](crate::status)。
+
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::email::Email;
+use crate::password::{Credential, Kdf, PasswordError};
+use crate::status::Status;
+use crate::username::Username;
+
+/// 一个用户账户
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    pub status: Status,
+    pub username: Username,
+    pub email: Email,
+    pub sign_in_count: u64,
+    /// 还没设置密码时是 `None`
+    pub password: Option<Credential>,
+    /// 最近一次成功登录的时间
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub last_sign_in: Option<SystemTime>,
+    /// 连续失败的登录次数，成功后清零
+    pub failed_sign_ins: u32,
+    /// 在这个时间之前不接受登录
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub locked_until: Option<SystemTime>,
+    /// 最近一次转到 `Active` 的时间
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub activated_at: Option<SystemTime>,
+    /// 最近一次被停用的时间
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub suspended_at: Option<SystemTime>,
+    #[serde(default, with = "crate::clock::unix_seconds")]
+    pub deleted_at: Option<SystemTime>,
+}
+
+impl User {
+    /// 设置或者更换密码。
+    pub fn set_password(&mut self, kdf: &Kdf, password: &str) -> Result<(), PasswordError> {
+        self.password = Some(kdf.hash(password)?);
+        Ok(())
+    }
+
+    /// 用密码登录：验证通过时 `sign_in_count` 加一，凭据参数过时的话换成按 `kdf` 重算的哈希。
+    pub fn authenticate(&mut self, kdf: &Kdf, password: &str) -> Result<(), PasswordError> {
+        let credential = self.password.as_ref().ok_or(PasswordError::NoPassword)?;
+        if let Some(rehashed) = kdf.verify(credential, password)? {
+            self.password = Some(rehashed);
+        }
+        self.sign_in_count += 1;
+        Ok(())
+    }
+}
+
+/// 新建一个 `Active` 的用户，登录次数从 1 开始。其他初始值见 [`UserBuilder`](crate::UserBuilder)。
+pub fn build_user(email: Email, username: Username) -> User {
+    User {
+        status: Status::Active,
+        username,
+        email,
+        sign_in_count: 1,
+        password: None,
+        last_sign_in: None,
+        failed_sign_ins: 0,
+        locked_until: None,
+        activated_at: None,
+        suspended_at: None,
+        deleted_at: None,
+    }
+}
@@ -0,0 +1,276 @@
+//! 用户名规则
+//!
+//! [`UsernamePolicy`] 规定用户名的长度、可以用哪些字符、哪些名字保留不给用，并且用 Unicode 骨架
+//! （UTS #39 skeleton）拦下和保留名长得一样的名字，例如用西里尔字母 `а` 拼出来的 `аdmin`。
+//! 检查时不在第一条违规处停下，而是把每一条违反的规则都列出来，注册失败时能说清楚原因。
+//!
+//! 通过检查的用户名是 [`Username`]，和 [`Email`](crate::Email) 一样，拿到它就说明已经校验过。
+//! 规则只在起名的时候检查：从文件或数据库读回来的名字当初可能是按别的策略（比如
+//! [`UserBuilder::policy`](crate::UserBuilder::policy)）起的，反序列化时原样接受，不再套用默认规则。
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use unicode_security::confusable_detection::skeleton;
+
+/// 允许出现在用户名里的一类字符
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharClass {
+    AsciiLowercase,
+    AsciiUppercase,
+    AsciiDigit,
+    /// 任何 Unicode 字母
+    Letter,
+    /// 任何 Unicode 数字
+    Number,
+    /// 列出来的这些字符
+    Chars(String),
+}
+
+impl CharClass {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            CharClass::AsciiLowercase => c.is_ascii_lowercase(),
+            CharClass::AsciiUppercase => c.is_ascii_uppercase(),
+            CharClass::AsciiDigit => c.is_ascii_digit(),
+            CharClass::Letter => c.is_alphabetic(),
+            CharClass::Number => c.is_numeric(),
+            CharClass::Chars(chars) => chars.contains(c),
+        }
+    }
+}
+
+/// 违反的一条规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// 开头或结尾有空白
+    SurroundingWhitespace,
+    TooShort {
+        min: usize,
+        len: usize,
+    },
+    TooLong {
+        max: usize,
+        len: usize,
+    },
+    /// 不在允许的字符类里的字符，每个只列一次
+    DisallowedChars(Vec<char>),
+    /// 和保留名相同（不区分大小写）
+    Reserved(String),
+    /// 和保留名的骨架相同，看起来一样但其实是别的字符
+    Confusable(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::SurroundingWhitespace => {
+                write!(f, "must not start or end with whitespace")
+            }
+            Violation::TooShort { min, len } => {
+                write!(f, "must be at least {min} characters, got {len}")
+            }
+            Violation::TooLong { max, len } => {
+                write!(f, "must be at most {max} characters, got {len}")
+            }
+            Violation::DisallowedChars(chars) => {
+                let chars: Vec<String> = chars.iter().map(|c| format!("{c:?}")).collect();
+                write!(f, "contains disallowed characters {}", chars.join(", "))
+            }
+            Violation::Reserved(name) => write!(f, "`{name}` is reserved"),
+            Violation::Confusable(name) => write!(f, "looks like the reserved name `{name}`"),
+        }
+    }
+}
+
+/// 用户名没有通过检查，带着每一条违反的规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsernameError {
+    pub username: String,
+    pub violations: Vec<Violation>,
+}
+
+impl fmt::Display for UsernameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid username {:?}: ", self.username)?;
+        let violations: Vec<String> = self.violations.iter().map(Violation::to_string).collect();
+        f.write_str(&violations.join("; "))
+    }
+}
+
+impl std::error::Error for UsernameError {}
+
+/// 用户名规则，可以按需修改各个字段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsernamePolicy {
+    /// 最短和最长的字符数（按 `char` 计）
+    pub min_len: usize,
+    pub max_len: usize,
+    pub allowed: Vec<CharClass>,
+    pub reserved: Vec<String>,
+}
+
+impl Default for UsernamePolicy {
+    /// 3 到 32 个字符，字母、数字和 `_-.`，保留常见的系统账户名。
+    fn default() -> Self {
+        UsernamePolicy {
+            min_len: 3,
+            max_len: 32,
+            allowed: vec![
+                CharClass::Letter,
+                CharClass::Number,
+                CharClass::Chars("_-.".into()),
+            ],
+            reserved: [
+                "admin",
+                "administrator",
+                "root",
+                "system",
+                "support",
+                "security",
+                "postmaster",
+                "webmaster",
+                "null",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+/// 比较外观用的骨架：先统一小写，大小写不同不算“看起来不一样”
+fn fold(name: &str) -> String {
+    skeleton(&name.to_lowercase()).collect()
+}
+
+impl UsernamePolicy {
+    /// 列出 `username` 违反的所有规则，没有违规时返回空列表。
+    pub fn violations(&self, username: &str) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        if username.trim() != username {
+            violations.push(Violation::SurroundingWhitespace);
+        }
+
+        let len = username.chars().count();
+        if len < self.min_len {
+            violations.push(Violation::TooShort {
+                min: self.min_len,
+                len,
+            });
+        }
+        if len > self.max_len {
+            violations.push(Violation::TooLong {
+                max: self.max_len,
+                len,
+            });
+        }
+
+        let mut disallowed = Vec::new();
+        for c in username.trim().chars() {
+            if !self.allowed.iter().any(|class| class.contains(c)) && !disallowed.contains(&c) {
+                disallowed.push(c);
+            }
+        }
+        if !disallowed.is_empty() {
+            violations.push(Violation::DisallowedChars(disallowed));
+        }
+
+        let lowercase = username.trim().to_lowercase();
+        let folded = fold(username.trim());
+        for reserved in &self.reserved {
+            if lowercase == reserved.to_lowercase() {
+                violations.push(Violation::Reserved(reserved.clone()));
+            } else if folded == fold(reserved) {
+                violations.push(Violation::Confusable(reserved.clone()));
+            }
+        }
+        violations
+    }
+
+    /// 按这套规则检查，通过时得到 [`Username`]。
+    pub fn parse(&self, username: &str) -> Result<Username, UsernameError> {
+        let violations = self.violations(username);
+        if violations.is_empty() {
+            Ok(Username(username.to_string()))
+        } else {
+            Err(UsernameError {
+                username: username.to_string(),
+                violations,
+            })
+        }
+    }
+
+    /// `taken` 里和 `username` 外观相同但并不相同的名字，用来拦下冒充已有账户的注册。
+    pub fn confusable_with<'a>(
+        &self,
+        username: &str,
+        taken: impl IntoIterator<Item = &'a str>,
+    ) -> Option<&'a str> {
+        let folded = fold(username);
+        taken
+            .into_iter()
+            .find(|name| *name != username && fold(name) == folded)
+    }
+}
+
+/// 通过了用户名规则检查的名字；反序列化时不再检查，见模块说明
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Username(String);
+
+impl Username {
+    /// 按默认规则检查。
+    pub fn parse(username: &str) -> Result<Self, UsernameError> {
+        UsernamePolicy::default().parse(username)
+    }
+
+    /// 比较外观用的骨架，两个名字骨架相同就算看起来一样
+    pub(crate) fn skeleton(&self) -> String {
+        fold(&self.0)
+    }
+
+    /// 存下来的名字，起名时已经按当时的策略检查过，原样接受。
+    pub fn from_stored(username: impl Into<String>) -> Self {
+        Username(username.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Username {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Username {
+    type Err = UsernameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Username::parse(s)
+    }
+}
+
+impl TryFrom<String> for Username {
+    type Error = UsernameError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Username::parse(&s)
+    }
+}
+
+impl From<Username> for String {
+    fn from(username: Username) -> Self {
+        username.0
+    }
+}
+
+impl AsRef<str> for Username {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
@@ -0,0 +1,155 @@
+//角色继承上级角色的全部权限；策略文件里的错误在加载时报出，只有 Active 的账户能用分到的权限；
+//角色跟着账户编号走，改名不丢，删掉后同名的新账户也拿不到；内存和 SQLite 存储都能用。
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use users::{
+    build_user, MemoryStorage, Permission, Policy, PolicyError, SqliteStorage, Storage, User,
+    UserId,
+};
+
+const POLICY: &str = r#"
+[roles.viewer]
+permissions = ["read_users", "read_audit_log"]
+
+[roles.moderator]
+inherits = ["viewer"]
+permissions = ["suspend_users"]
+
+[roles.admin]
+inherits = ["moderator"]
+permissions = ["create_users", "update_users", "delete_users", "manage_roles"]
+
+[assignments]
+alice = ["admin"]
+bob = ["moderator"]
+"#;
+
+fn user(name: &str) -> User {
+    build_user(
+        format!("{name}@example.com").parse().unwrap(),
+        name.parse().unwrap(),
+    )
+}
+
+/// alice、bob、carol 依次是 #1、#2、#3
+fn storage<S: Storage>(mut storage: S) -> S {
+    for name in ["alice", "bob", "carol"] {
+        storage.insert(&user(name)).unwrap();
+    }
+    storage
+}
+
+/// 从存储里取出账户再检查
+fn can(storage: &impl Storage, policy: &Policy, id: UserId, permission: Permission) -> bool {
+    storage
+        .get(id)
+        .unwrap()
+        .is_some_and(|user| user.can(id, policy, permission))
+}
+
+const ALICE: UserId = UserId(1);
+const BOB: UserId = UserId(2);
+const CAROL: UserId = UserId(3);
+
+#[test]
+fn roles_inherit_permissions() {
+    let storage = storage(MemoryStorage::new());
+    let policy = Policy::from_toml(POLICY, &storage).unwrap();
+    let admin = policy.permissions("admin").unwrap();
+    assert!(admin.contains(&Permission::ReadUsers));
+    assert!(admin.contains(&Permission::SuspendUsers));
+    assert!(!admin.contains(&Permission::ManageSessions));
+
+    assert!(can(&storage, &policy, ALICE, Permission::DeleteUsers));
+    assert!(can(&storage, &policy, BOB, Permission::ReadAuditLog));
+    assert!(!can(&storage, &policy, BOB, Permission::DeleteUsers));
+    assert!(!can(&storage, &policy, CAROL, Permission::ReadUsers));
+}
+
+#[test]
+fn assignments_change_and_inactive_users_lose_access() {
+    let mut storage = storage(MemoryStorage::new());
+    let mut policy = Policy::from_toml(POLICY, &storage).unwrap();
+    policy.assign(CAROL, "viewer").unwrap();
+    assert!(can(&storage, &policy, CAROL, Permission::ReadUsers));
+    assert_eq!(policy.roles_of(CAROL).collect::<Vec<_>>(), ["viewer"]);
+
+    let mut carol = storage.get(CAROL).unwrap().unwrap();
+    carol.suspend("spam", SystemTime::now()).unwrap();
+    storage.update(CAROL, &carol).unwrap();
+    assert!(!carol.can(CAROL, &policy, Permission::ReadUsers));
+
+    assert!(policy.unassign(CAROL, "viewer"));
+    assert!(!policy.unassign(CAROL, "viewer"));
+    assert!(matches!(
+        policy.assign(CAROL, "root"),
+        Err(PolicyError::UnknownRole { .. })
+    ));
+}
+
+fn roles_follow_the_account(mut storage: impl Storage) {
+    let policy = Policy::from_toml(POLICY, &storage).unwrap();
+
+    let mut bob = storage.get(BOB).unwrap().unwrap();
+    bob.username = "robert".parse().unwrap();
+    storage.update(BOB, &bob).unwrap();
+    assert!(can(&storage, &policy, BOB, Permission::SuspendUsers));
+
+    storage.remove(ALICE).unwrap();
+    assert!(!can(&storage, &policy, ALICE, Permission::DeleteUsers));
+    let impostor = storage.insert(&user("alice")).unwrap();
+    assert_ne!(impostor, ALICE);
+    assert!(!can(&storage, &policy, impostor, Permission::ReadUsers));
+}
+
+#[test]
+fn roles_follow_the_account_not_the_name() {
+    roles_follow_the_account(storage(MemoryStorage::new()));
+    roles_follow_the_account(storage(SqliteStorage::open_in_memory().unwrap()));
+}
+
+#[test]
+fn invalid_policies_are_rejected_on_load() {
+    let cycle = "[roles.a]\ninherits = [\"b\"]\n[roles.b]\ninherits = [\"a\"]\n";
+    let storage = storage(MemoryStorage::new());
+    let Err(PolicyError::Cycle(roles)) = Policy::from_toml(cycle, &storage) else {
+        panic!("cycle not detected");
+    };
+    assert_eq!(roles, ["a", "b", "a"]);
+
+    let unknown = "[roles.a]\ninherits = [\"ghost\"]\n";
+    assert_eq!(
+        Policy::from_toml(unknown, &storage)
+            .unwrap_err()
+            .to_string(),
+        "unknown role `ghost` referenced by `a`"
+    );
+    let assigned = "[assignments]\nalice = [\"ghost\"]\n";
+    assert!(matches!(
+        Policy::from_toml(assigned, &storage),
+        Err(PolicyError::UnknownRole { .. })
+    ));
+    let nobody = "[roles.a]\n[assignments]\nmallory = [\"a\"]\n";
+    assert_eq!(
+        Policy::from_toml(nobody, &storage).unwrap_err().to_string(),
+        "unknown user `mallory` in assignments"
+    );
+    let permission = "[roles.a]\npermissions = [\"launch_missiles\"]\n";
+    assert!(matches!(
+        Policy::from_toml(permission, &storage),
+        Err(PolicyError::Parse(_))
+    ));
+}
+
+#[test]
+fn policies_load_from_files() {
+    let storage = storage(SqliteStorage::open_in_memory().unwrap());
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("access-policy.toml");
+    fs::write(&path, POLICY).unwrap();
+    let policy = Policy::load(&path, &storage).unwrap();
+    assert!(can(&storage, &policy, ALICE, Permission::ManageRoles));
+    assert!(!can(&storage, &policy, CAROL, Permission::ReadUsers));
+}
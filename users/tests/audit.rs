@@ -0,0 +1,240 @@
+//每次修改都记成事件，包括登录失败和重算哈希；重放事件能重建出和注册表一样的状态（除了密码）；
+//可以查一个账户的全部历史。
+
+use std::time::{Duration, SystemTime};
+
+use users::{
+    build_user, Actor, AuditError, AuditLog, Authenticator, Change, Clock, Kdf, ManualClock,
+    RegistryError, ReplayError, SignInError, SignInPolicy, Status, SystemClock, TransitionError,
+    User, UserId, UserPatch, UserRegistry,
+};
+
+#[test]
+fn changes_are_recorded_with_time_and_actor() {
+    let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(100));
+    let mut log = AuditLog::new(&clock);
+    let mut registry = UserRegistry::new();
+    let alice = build_user(
+        "alice@example.com".parse().unwrap(),
+        "alice".parse().unwrap(),
+    );
+    let id = log.create(&mut registry, Actor::System, alice).unwrap();
+    clock.advance(Duration::from_secs(5));
+    log.change_email(
+        &mut registry,
+        id,
+        Actor::User(id),
+        "alice@example.org".parse().unwrap(),
+    )
+    .unwrap();
+    clock.advance(Duration::from_secs(5));
+    let suspended = Status::Suspended {
+        reason: "spam".into(),
+    };
+    log.change_status(&mut registry, id, Actor::System, suspended.clone())
+        .unwrap();
+    // 不允许的转移不记
+    assert!(matches!(
+        log.change_status(&mut registry, id, Actor::System, suspended),
+        Err(AuditError::Transition(_))
+    ));
+
+    let lines: Vec<String> = log.history(id).map(ToString::to_string).collect();
+    assert_eq!(
+        lines,
+        [
+            "0 [100] #1 by system: created `alice`",
+            "1 [105] #1 by #1: email changed from alice@example.com to alice@example.org",
+            "2 [110] #1 by system: status changed from active to suspended: spam",
+        ]
+    );
+}
+
+#[test]
+fn replay_rebuilds_current_state() {
+    let clock = ManualClock::default();
+    let auth = Authenticator::new(Kdf::new(64, 1, 1).unwrap(), SignInPolicy::default(), &clock);
+    let mut log = AuditLog::new(&clock);
+    let mut registry = UserRegistry::new();
+
+    // 按旧参数算的哈希，登录成功时会重算
+    let mut bob = build_user("bob@example.com".parse().unwrap(), "bob".parse().unwrap());
+    bob.set_password(&Kdf::new(32, 1, 1).unwrap(), "hunter2")
+        .unwrap();
+    let bob = log.create(&mut registry, Actor::System, bob).unwrap();
+    let carol = build_user(
+        "carol@example.com".parse().unwrap(),
+        "carol".parse().unwrap(),
+    );
+    let carol = log.create(&mut registry, Actor::System, carol).unwrap();
+
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(
+        log.sign_in(&mut registry, &auth, "bob", "wrong"),
+        Err(SignInError::InvalidCredentials)
+    );
+    clock.advance(Duration::from_secs(1));
+    let before = registry.get(bob).unwrap().password.clone();
+    assert_eq!(log.sign_in(&mut registry, &auth, "bob", "hunter2"), Ok(bob));
+    assert_ne!(registry.get(bob).unwrap().password, before);
+    log.change_status(&mut registry, carol, Actor::User(bob), Status::Deleted)
+        .unwrap();
+    let patch = UserPatch {
+        username: Some("robert".parse().unwrap()),
+        ..UserPatch::default()
+    };
+    log.apply_patch(&mut registry, bob, Actor::User(bob), &patch)
+        .unwrap();
+
+    // 密码哈希不进日志，重放出来的账户没有密码
+    let replayed = log.replay().unwrap();
+    let current: Vec<_> = registry
+        .iter()
+        .map(|(id, user)| {
+            (
+                id,
+                User {
+                    password: None,
+                    ..user.clone()
+                },
+            )
+        })
+        .collect();
+    assert_eq!(replayed.into_iter().collect::<Vec<_>>(), current);
+    assert_eq!(
+        log.replay_user(carol).unwrap().as_ref(),
+        registry.get(carol)
+    );
+    assert_eq!(log.replay_user(UserId(9)).unwrap(), None);
+
+    let lines: Vec<String> = log.history(bob).map(|e| e.change.to_string()).collect();
+    assert_eq!(
+        lines,
+        [
+            "created `bob`",
+            "password changed",
+            "sign-in failed (1 in a row)",
+            "signed in",
+            "password rehashed",
+            "updated username",
+        ]
+    );
+    let logged = serde_json::to_string(log.events()).unwrap();
+    assert!(!logged.contains("argon2"), "{logged}");
+}
+
+#[test]
+fn replay_matches_the_registry_with_separate_clocks() {
+    // 日志用系统时钟，登录用另一只时钟：事件时间必须是修改本身用的那个
+    let auth_clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1000));
+    let kdf = Kdf::new(64, 1, 1).unwrap();
+    let auth = Authenticator::new(kdf.clone(), SignInPolicy::default(), &auth_clock);
+    let mut log = AuditLog::new(SystemClock);
+    let mut registry = UserRegistry::new();
+    let mut dave = build_user("dave@example.com".parse().unwrap(), "dave".parse().unwrap());
+    dave.status = Status::Pending;
+    dave.set_password(&kdf, "hunter2").unwrap();
+    let dave = log.create(&mut registry, Actor::System, dave).unwrap();
+    log.change_status(&mut registry, dave, Actor::System, Status::Active)
+        .unwrap();
+    assert_eq!(
+        log.sign_in(&mut registry, &auth, "dave", "hunter2"),
+        Ok(dave)
+    );
+    log.change_status(&mut registry, dave, Actor::System, Status::Deleted)
+        .unwrap();
+
+    let current = User {
+        password: None,
+        ..registry.get(dave).unwrap().clone()
+    };
+    assert_eq!(log.replay_user(dave).unwrap(), Some(current));
+}
+
+#[test]
+fn status_changes_follow_the_state_machine() {
+    let clock = ManualClock::default();
+    let mut log = AuditLog::new(&clock);
+    let mut registry = UserRegistry::new();
+    let erin = build_user("erin@example.com".parse().unwrap(), "erin".parse().unwrap());
+    let erin = log.create(&mut registry, Actor::System, erin).unwrap();
+    clock.advance(Duration::from_secs(10));
+    log.update(&mut registry, erin, Actor::System, |user| {
+        user.status = Status::Deleted;
+    })
+    .unwrap();
+    let deleted = registry.get(erin).unwrap().clone();
+    assert_eq!(deleted.deleted_at, Some(clock.now()));
+
+    // 删掉的账户改不回去，补丁也带不了状态
+    assert_eq!(
+        log.update(&mut registry, erin, Actor::System, |user| {
+            user.status = Status::Active;
+        }),
+        Err(AuditError::Transition(TransitionError {
+            from: Status::Deleted,
+            to: Status::Active,
+        }))
+    );
+    assert!(serde_json::from_str::<UserPatch>(r#"{"status":"active"}"#).is_err());
+    let patch = UserPatch {
+        sign_in_count: Some(7),
+        ..UserPatch::default()
+    };
+    log.apply_patch(&mut registry, erin, Actor::System, &patch)
+        .unwrap();
+    assert_eq!(registry.get(erin).unwrap().status, Status::Deleted);
+
+    let lines: Vec<String> = log.history(erin).map(|e| e.change.to_string()).collect();
+    assert_eq!(
+        lines,
+        [
+            "created `erin`",
+            "status changed from active to deleted",
+            "updated sign_in_count",
+        ]
+    );
+    assert_eq!(log.replay_user(erin).unwrap().as_ref(), registry.get(erin));
+}
+
+#[test]
+fn rejected_changes_leave_no_trace() {
+    let mut log = AuditLog::new(ManualClock::default());
+    let mut registry = UserRegistry::new();
+    let user = |email: &str, name: &str| build_user(email.parse().unwrap(), name.parse().unwrap());
+    let a = log
+        .create(&mut registry, Actor::System, user("a@example.com", "aaa"))
+        .unwrap();
+    log.create(&mut registry, Actor::System, user("b@example.com", "bbb"))
+        .unwrap();
+    assert_eq!(
+        log.change_email(
+            &mut registry,
+            a,
+            Actor::System,
+            "B@example.com".parse().unwrap()
+        ),
+        Err(RegistryError::DuplicateEmail("B@example.com".into()))
+    );
+    assert!(log
+        .create(&mut registry, Actor::System, user("c@example.com", "aaa"))
+        .is_err());
+    assert_eq!(log.events().len(), 2);
+
+    // 事件顺序不对时重放报错
+    let events = vec![users::Event {
+        seq: 0,
+        user: a,
+        at: SystemTime::UNIX_EPOCH,
+        actor: Actor::System,
+        change: Change::StatusChanged {
+            from: Status::Active,
+            to: Status::Deleted,
+        },
+    }];
+    let log = AuditLog::from_events(events, ManualClock::default());
+    assert_eq!(
+        log.replay(),
+        Err(ReplayError::NotCreated { seq: 0, user: a })
+    );
+}
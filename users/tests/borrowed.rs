@@ -0,0 +1,134 @@
+//UserRef 的字符串字段直接指向输入缓冲区；转成 User 时才校验，结果和 persist 读出来的一样。
+
+use std::ops::Range;
+use std::time::{Duration, SystemTime};
+
+use users::borrowed::parse_csv;
+use users::persist::{self, Format};
+use users::{build_user, Kdf, ParseError, RefFieldError, User, UserRef};
+
+fn users() -> Vec<User> {
+    let mut alice = build_user(
+        "alice@example.com".parse().unwrap(),
+        "alice".parse().unwrap(),
+    );
+    alice
+        .set_password(&Kdf::new(64, 1, 1).unwrap(), "correct horse")
+        .unwrap();
+    alice.last_sign_in = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    let mut bob = build_user(
+        "bob@bücher.example".parse().unwrap(),
+        "bob".parse().unwrap(),
+    );
+    bob.suspend("spam", SystemTime::UNIX_EPOCH + Duration::from_secs(60))
+        .unwrap();
+    bob.failed_sign_ins = 3;
+    vec![alice, bob]
+}
+
+fn within(buffer: &str, field: &str) -> bool {
+    let Range { start, end } = buffer.as_bytes().as_ptr_range();
+    let ptr = field.as_ptr();
+    start <= ptr && ptr < end
+}
+
+#[test]
+fn borrows_from_the_csv_buffer() {
+    let users = users();
+    let csv = persist::to_string(&users, Format::Csv).unwrap();
+    let refs: Vec<UserRef> = parse_csv(&csv).unwrap().collect::<Result<_, _>>().unwrap();
+    assert_eq!(refs.len(), 2);
+    for user in &refs {
+        assert!(within(&csv, user.username));
+        assert!(within(&csv, user.email));
+    }
+    assert!(within(&csv, refs[0].password.unwrap()));
+    assert_eq!(refs[1].password, None);
+
+    let owned: Vec<User> = refs.into_iter().map(|r| r.to_owned().unwrap()).collect();
+    assert_eq!(owned, users);
+    assert_eq!(owned, persist::from_str(&csv, Format::Csv).unwrap());
+}
+
+#[test]
+fn borrows_from_a_json_line() {
+    let line = r#"{"status":"pending","username":"carol","email":"carol@example.com",
+        "sign_in_count":2,"password":null,"failed_sign_ins":0}"#;
+    let user: UserRef = serde_json::from_str(line).unwrap();
+    assert!(within(line, user.username));
+    assert_eq!(user.last_sign_in, None);
+    assert_eq!(
+        User::try_from(user).unwrap().email.as_str(),
+        "carol@example.com"
+    );
+}
+
+#[test]
+fn format_errors_carry_line_numbers() {
+    let header = "version,status,username,email,sign_in_count,password,last_sign_in,\
+                  failed_sign_ins,locked_until,activated_at,suspended_at,deleted_at";
+    assert_eq!(parse_csv("id,name\n").unwrap_err(), ParseError::Header);
+    assert_eq!(parse_csv("").unwrap().count(), 0);
+
+    let rows = [
+        (
+            "4,active,alice,a@example.com,1,,,0,,,",
+            ParseError::FieldCount { line: 2, found: 11 },
+        ),
+        (
+            "4,active,alice,a@example.com,1,,,0,,soon,,",
+            ParseError::InvalidField {
+                line: 2,
+                field: "activated_at",
+            },
+        ),
+        (
+            "4,active,alice,\"a\"\"b\"@example.com,1,,,0,,,,",
+            ParseError::Escaped { line: 2 },
+        ),
+        (
+            "4,active,alice,\"a@example.com,1,,,0,,,,",
+            ParseError::Unterminated { line: 2 },
+        ),
+        (
+            "3,active,alice,a@example.com,1,,,0,,,,",
+            ParseError::Version { line: 2 },
+        ),
+    ];
+    for (row, expected) in rows {
+        let input = format!("{header}\n{row}\n");
+        let result = parse_csv(&input).unwrap().next().unwrap();
+        assert_eq!(result.unwrap_err(), expected, "{row}");
+    }
+
+    // 带引号的逗号属于字段本身
+    let input = format!("{header}\n4,active,\"al,ice\",a@example.com,1,,,0,,,,\n");
+    let user = parse_csv(&input).unwrap().next().unwrap().unwrap();
+    assert_eq!(user.username, "al,ice");
+}
+
+#[test]
+fn to_owned_reports_every_invalid_field() {
+    let user = UserRef {
+        status: "frozen",
+        username: "root",
+        email: "nope",
+        sign_in_count: 1,
+        password: Some("$argon2id$broken"),
+        last_sign_in: None,
+        failed_sign_ins: 0,
+        locked_until: None,
+        activated_at: None,
+        suspended_at: None,
+        deleted_at: None,
+    };
+    let errors = user.to_owned().unwrap_err().errors;
+    assert!(matches!(
+        errors.as_slice(),
+        [
+            RefFieldError::Status(_),
+            RefFieldError::Email(_),
+            RefFieldError::Password(_)
+        ]
+    ));
+}
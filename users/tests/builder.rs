@@ -0,0 +1,69 @@
+//构造器填上默认值；邮箱和用户名一起校验，错误一次全部返回。
+
+use users::{build_user, FieldError, Kdf, Status, UserBuilder, UsernamePolicy, Violation};
+
+#[test]
+fn defaults_match_build_user() {
+    let user = UserBuilder::new()
+        .username("alice")
+        .email("alice@example.com")
+        .build()
+        .unwrap();
+    assert_eq!(
+        user,
+        build_user(
+            "alice@example.com".parse().unwrap(),
+            "alice".parse().unwrap()
+        )
+    );
+}
+
+#[test]
+fn optional_fields_override_defaults() {
+    let credential = Kdf::new(64, 1, 1).unwrap().hash("hunter2").unwrap();
+    let user = UserBuilder::new()
+        .status(Status::Pending)
+        .email("bob@example.com")
+        .sign_in_count(0)
+        .password(credential.clone())
+        .username("bob")
+        .build()
+        .unwrap();
+    assert_eq!(user.status, Status::Pending);
+    assert!(!user.is_active());
+    assert_eq!(user.sign_in_count, 0);
+    assert_eq!(user.password, Some(credential));
+}
+
+#[test]
+fn collects_every_error() {
+    let err = UserBuilder::new()
+        .email("not an email")
+        .username("x")
+        .build()
+        .unwrap_err();
+    assert_eq!(err.errors.len(), 2);
+    assert!(matches!(err.errors[0], FieldError::Email(_)));
+    let FieldError::Username(username) = &err.errors[1] else {
+        panic!("{err}");
+    };
+    assert_eq!(
+        username.violations,
+        [Violation::TooShort { min: 3, len: 1 }]
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid user: email address has no `@`; \
+         invalid username \"x\": must be at least 3 characters, got 1"
+    );
+
+    let lenient = UsernamePolicy {
+        min_len: 1,
+        ..UsernamePolicy::default()
+    };
+    let user = UserBuilder::new()
+        .policy(lenient)
+        .email("x@example.com")
+        .username("x");
+    assert!(user.build().is_ok());
+}
@@ -0,0 +1,86 @@
+//按 RFC 5322 addr-spec 解析邮箱：本地部分可以是 UTF-8 或带引号，域名按 IDNA 规范化成小写。
+
+use users::{build_user, Email, EmailError};
+
+#[test]
+fn accepts_addr_spec_forms() {
+    for address in [
+        "someone@example.com",
+        "first.last+tag@sub.example.co",
+        "!#$%&'*+-/=?^_`{|}~@example.com",
+        r#""john doe"@example.com"#,
+        r#""a\"b@c"@example.com"#,
+        "用户@example.com",
+        "someone@[192.168.0.1]",
+        "someone@localhost",
+    ] {
+        assert!(Email::parse(address).is_ok(), "{address}");
+    }
+}
+
+#[test]
+fn rejects_malformed_addresses() {
+    let cases = [
+        ("", EmailError::Empty),
+        ("someone.example.com", EmailError::MissingAt),
+        (
+            ".someone@example.com",
+            EmailError::InvalidLocalPart(".someone".into()),
+        ),
+        (
+            "some..one@example.com",
+            EmailError::InvalidLocalPart("some..one".into()),
+        ),
+        (
+            "some one@example.com",
+            EmailError::InvalidLocalPart("some one".into()),
+        ),
+        (
+            r#""unterminated@example.com"#,
+            EmailError::InvalidLocalPart(r#""unterminated"#.into()),
+        ),
+        ("someone@", EmailError::InvalidDomain("".into())),
+        (
+            "someone@example..com",
+            EmailError::InvalidDomain("example..com".into()),
+        ),
+        (
+            "someone@exa_mple.com",
+            EmailError::InvalidDomain("exa_mple.com".into()),
+        ),
+        (
+            "someone@-example.com",
+            EmailError::InvalidDomain("-example.com".into()),
+        ),
+    ];
+    for (address, error) in cases {
+        assert_eq!(Email::parse(address), Err(error), "{address}");
+    }
+    let long_local = format!("{}@example.com", "a".repeat(65));
+    assert_eq!(Email::parse(&long_local), Err(EmailError::LocalPartTooLong));
+    let long = format!("a@{}.com", vec!["b".repeat(60); 5].join("."));
+    assert!(Email::parse(&long).is_err());
+}
+
+#[test]
+fn normalizes_domain_but_not_local_part() {
+    let email: Email = "Some.One@EXAMPLE.Com".parse().unwrap();
+    assert_eq!(email.local_part(), "Some.One");
+    assert_eq!(email.domain(), "example.com");
+    assert_eq!(email.to_string(), "Some.One@example.com");
+
+    let unicode = Email::parse("josé@Bücher.EXAMPLE").unwrap();
+    assert_eq!(unicode.domain(), "bücher.example");
+    assert_eq!(unicode.ascii_domain(), "xn--bcher-kva.example");
+    assert_eq!(Email::parse("josé@xn--bcher-kva.example").unwrap(), unicode);
+}
+
+#[test]
+fn user_holds_a_validated_email() {
+    let user = build_user(
+        "someone@Example.com".parse().unwrap(),
+        "someone".parse().unwrap(),
+    );
+    assert_eq!(user.email.domain(), "example.com");
+    assert!(Email::try_from(String::from("not an email")).is_err());
+}
@@ -0,0 +1,165 @@
+//旧版本的记录沿着迁移链升级到当前版本；试运行只报告改动，不写文件；persist 存的文件迁移后原样读回。
+
+use std::path::PathBuf;
+
+use users::migrate::{self, Envelope, CURRENT_VERSION};
+use users::persist::{self, Format};
+use users::{build_user, MigrateError, Mode, PersistError, Status, User};
+
+const V1: &str = r#"[
+    {"active": true, "username": "alice", "email": "alice@example.com", "sign_in_count": 3},
+    {"version": 2, "record": {"active": false, "username": "bob",
+        "email": "bob@example.com", "sign_in_count": 1, "password": null}}
+]"#;
+
+fn file(name: &str, contents: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("migrate");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn upgrades_old_records_step_by_step() {
+    let report = migrate::load(V1, Format::Json).unwrap();
+    assert_eq!(report.users.len(), 2);
+    assert_eq!(report.users[0].username.as_str(), "alice");
+    assert_eq!(report.users[0].sign_in_count, 3);
+    assert_eq!(report.users[0].password, None);
+    assert_eq!(report.users[0].failed_sign_ins, 0);
+    assert!(report.users[0].is_active());
+    assert_eq!(
+        report.users[1].status,
+        Status::Suspended {
+            reason: "deactivated".into()
+        }
+    );
+
+    let alice = &report.changes[0];
+    assert_eq!((alice.from, alice.to), (1, CURRENT_VERSION));
+    assert_eq!(
+        alice.edits,
+        [
+            "add `password` = null",
+            "add `last_sign_in` = null",
+            "add `failed_sign_ins` = 0",
+            "add `locked_until` = null",
+            "replace `active` = true with `status` = \"active\"",
+            "add `activated_at` = null",
+            "add `suspended_at` = null",
+            "add `deleted_at` = null",
+        ]
+    );
+    // 版本 2 从第二步开始
+    assert_eq!(report.changes[1].from, 2);
+    assert_eq!(report.changes[1].edits.len(), 7);
+}
+
+#[test]
+fn current_records_are_left_alone() {
+    let users = vec![build_user(
+        "carol@example.com".parse().unwrap(),
+        "carol".parse().unwrap(),
+    )];
+    for format in [Format::Json, Format::Toml, Format::Csv] {
+        let text = persist::to_string(&users, format).unwrap();
+        let report = migrate::load(&text, format).unwrap();
+        assert!(report.changes.is_empty(), "{format:?}: {report}");
+        assert_eq!(report.users, users);
+        assert_eq!(report.to_string(), "all 1 records are at version 4\n");
+    }
+
+    // 没有信封但已经是当前形状的裸记录不算旧版本
+    let bare = serde_json::to_string(&users).unwrap();
+    let report = migrate::load(&bare, Format::Json).unwrap();
+    assert!(report.changes.is_empty(), "{report}");
+    assert_eq!(report.users, users);
+}
+
+#[test]
+fn old_files_load_in_every_format() {
+    let toml = r#"
+        [[users]]
+        active = false
+        username = "alice"
+        email = "alice@example.com"
+        sign_in_count = 3
+    "#;
+    let csv = "active,username,email,sign_in_count\nfalse,alice,alice@example.com,3\n";
+    for (text, format) in [(toml, Format::Toml), (csv, Format::Csv)] {
+        let users = persist::from_str(text, format).unwrap();
+        assert_eq!(users[0].sign_in_count, 3, "{format:?}");
+        assert_eq!(
+            users[0].status,
+            Status::Suspended {
+                reason: "deactivated".into()
+            }
+        );
+    }
+    let users = persist::from_str(V1, Format::Json).unwrap();
+    assert!(users[0].is_active());
+}
+
+#[test]
+fn saved_files_survive_migration() {
+    let users = vec![
+        build_user(
+            "alice@example.com".parse().unwrap(),
+            "alice".parse().unwrap(),
+        ),
+        build_user("bob@example.com".parse().unwrap(), "bob".parse().unwrap()),
+    ];
+    for name in ["round-trip.json", "round-trip.toml", "round-trip.csv"] {
+        let path = file(name, "");
+        persist::save(&path, &users).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        let report = migrate::migrate_file(&path, Mode::Apply).unwrap();
+        assert!(report.changes.is_empty(), "{name}: {report}");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), saved);
+        assert_eq!(persist::load(&path).unwrap(), users);
+    }
+
+    // 旧文件迁移之后 persist 照样能读
+    let path = file("upgraded.json", V1);
+    migrate::migrate_file(&path, Mode::Apply).unwrap();
+    let loaded: Vec<User> = persist::load(&path).unwrap();
+    assert_eq!(loaded[0].username.as_str(), "alice");
+}
+
+#[test]
+fn dry_run_reports_without_writing() {
+    let path = file("dry-run.json", V1);
+    let report = migrate::migrate_file(&path, Mode::DryRun).unwrap();
+    assert!(report
+        .to_string()
+        .starts_with("record 0 (alice): v1 -> v4\n  add `password` = null\n"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), V1);
+
+    let path = file("apply.json", V1);
+    let applied = migrate::migrate_file(&path, Mode::Apply).unwrap();
+    assert_eq!(applied, report);
+    let envelopes: Vec<Envelope> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(envelopes.iter().all(|e| e.version == CURRENT_VERSION));
+    let again = migrate::migrate_file(&path, Mode::DryRun).unwrap();
+    assert!(again.changes.is_empty());
+}
+
+#[test]
+fn rejects_unknown_versions_and_bad_records() {
+    let future = r#"[{"version": 9, "record": {}}]"#;
+    assert!(matches!(
+        migrate::load(future, Format::Json),
+        Err(PersistError::Migrate(MigrateError::UnsupportedVersion {
+            record: 0,
+            version: 9
+        }))
+    ));
+    let missing = r#"[{"version": 1, "record": {"active": true, "username": "dave"}}]"#;
+    let err = migrate::load(missing, Format::Json).unwrap_err();
+    assert!(
+        err.to_string().starts_with("record 0: missing field"),
+        "{err}"
+    );
+}
@@ -0,0 +1,76 @@
+//密码存成带盐的 Argon2id PHC 字符串；登录成功才增加 sign_in_count，参数变了就在登录时重算。
+
+use users::{build_user, Credential, Kdf, PasswordError, User};
+
+/// 测试里用很小的参数，免得太慢
+fn kdf(memory_kib: u32) -> Kdf {
+    Kdf::new(memory_kib, 1, 1).unwrap()
+}
+
+fn user() -> User {
+    build_user(
+        "someone@example.com".parse().unwrap(),
+        "someone".parse().unwrap(),
+    )
+}
+
+#[test]
+fn hashes_are_salted_phc_strings() {
+    let kdf = kdf(64);
+    let first = kdf.hash("hunter2").unwrap();
+    let second = kdf.hash("hunter2").unwrap();
+    assert!(first.as_phc().starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+    assert_ne!(first, second, "same password, different salt");
+    assert!(!format!("{first:?}").contains(first.as_phc().rsplit('$').next().unwrap()));
+
+    assert_eq!(kdf.verify(&first, "hunter2"), Ok(None));
+    assert_eq!(kdf.verify(&second, "hunter3"), Err(PasswordError::Mismatch));
+    assert_eq!(Credential::parse(first.as_phc()), Ok(first));
+    assert!(matches!(
+        Credential::parse("$2b$12$notargon"),
+        Err(PasswordError::InvalidHash(_))
+    ));
+}
+
+#[test]
+fn authenticate_counts_only_successful_sign_ins() {
+    let kdf = kdf(64);
+    let mut user = user();
+    assert_eq!(
+        user.authenticate(&kdf, "anything"),
+        Err(PasswordError::NoPassword)
+    );
+
+    user.set_password(&kdf, "correct horse").unwrap();
+    assert_eq!(
+        user.authenticate(&kdf, "wrong horse"),
+        Err(PasswordError::Mismatch)
+    );
+    assert_eq!(user.sign_in_count, 1);
+    user.authenticate(&kdf, "correct horse").unwrap();
+    assert_eq!(user.sign_in_count, 2);
+}
+
+#[test]
+fn stale_parameters_are_rehashed_on_login() {
+    let old = kdf(64);
+    let new = kdf(128);
+    let mut user = user();
+    user.set_password(&old, "correct horse").unwrap();
+    let before = user.password.clone().unwrap();
+    assert!(new.needs_rehash(&before));
+    assert!(!old.needs_rehash(&before));
+
+    // 旧哈希还能验证，验证完换成新参数
+    user.authenticate(&new, "correct horse").unwrap();
+    let after = user.password.clone().unwrap();
+    assert!(after.as_phc().contains("m=128,t=1,p=1"));
+    assert!(!new.needs_rehash(&after));
+
+    // 密码不对时不重算
+    assert_eq!(
+        user.authenticate(&old, "wrong"),
+        Err(PasswordError::Mismatch)
+    );
+    assert_eq!(user.password, Some(after));
+}
@@ -0,0 +1,118 @@
+//补丁原地应用、合并、求逆、求差，序列化时只带改了的字段；补丁里不能带状态。
+
+use std::time::{Duration, SystemTime};
+
+use users::{build_user, User, UserPatch, Username, UsernamePolicy};
+
+fn alice() -> User {
+    build_user(
+        "alice@example.com".parse().unwrap(),
+        "alice".parse().unwrap(),
+    )
+}
+
+#[test]
+fn apply_in_place_and_undo() {
+    let original = alice();
+    let mut user = original.clone();
+    let patch = UserPatch {
+        email: Some("alice@example.org".parse().unwrap()),
+        failed_sign_ins: Some(3),
+        locked_until: Some(Some(SystemTime::UNIX_EPOCH + Duration::from_secs(60))),
+        ..UserPatch::default()
+    };
+    let undo = patch.invert(&user);
+    patch.apply(&mut user);
+    // 补丁还能接着用，`original` 也没有被移走任何字段
+    assert_eq!(user.email.as_str(), "alice@example.org");
+    assert_eq!(user.failed_sign_ins, 3);
+    assert_eq!(user.username, original.username);
+    assert_eq!(patch.fields(), ["email", "failed_sign_ins", "locked_until"]);
+
+    undo.apply(&mut user);
+    assert_eq!(user, original);
+    assert_eq!(undo.locked_until, Some(None));
+}
+
+#[test]
+fn merge_later_wins() {
+    let first = UserPatch {
+        failed_sign_ins: Some(1),
+        sign_in_count: Some(3),
+        ..UserPatch::default()
+    };
+    let second = UserPatch {
+        failed_sign_ins: Some(2),
+        password: Some(None),
+        ..UserPatch::default()
+    };
+    let merged = first.clone().merge(second.clone());
+    assert_eq!(merged.failed_sign_ins, Some(2));
+    assert_eq!(merged.sign_in_count, Some(3));
+    assert_eq!(merged.password, Some(None));
+
+    let (mut one, mut two) = (alice(), alice());
+    first.apply(&mut one);
+    second.apply(&mut one);
+    merged.apply(&mut two);
+    assert_eq!(one, two);
+}
+
+#[test]
+fn diff_then_apply_reaches_target() {
+    let old = alice();
+    let mut new = old.clone();
+    new.username = "alice2".parse().unwrap();
+    new.failed_sign_ins = 2;
+    new.last_sign_in = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+    let patch = UserPatch::diff(&old, &new);
+    assert_eq!(
+        patch.fields(),
+        ["username", "last_sign_in", "failed_sign_ins"]
+    );
+    let mut user = old.clone();
+    patch.apply(&mut user);
+    assert_eq!(user, new);
+    assert!(UserPatch::diff(&new, &new).is_empty());
+}
+
+#[test]
+fn usernames_read_back_as_stored() {
+    // 按宽松的规则起的名字，求差得到的补丁序列化之后还能读回来
+    let lenient = UsernamePolicy {
+        min_len: 1,
+        ..UsernamePolicy::default()
+    };
+    let old = alice();
+    let mut new = old.clone();
+    new.username = lenient.parse("b").unwrap();
+    let patch = UserPatch::diff(&old, &new);
+    let json = serde_json::to_string(&patch).unwrap();
+    assert_eq!(json, r#"{"username":"b"}"#);
+    assert_eq!(serde_json::from_str::<UserPatch>(&json).unwrap(), patch);
+    assert!(Username::parse("b").is_err());
+}
+
+#[test]
+fn wire_format_only_has_changed_fields() {
+    let patch = UserPatch {
+        email: Some("bob@example.com".parse().unwrap()),
+        password: Some(None),
+        last_sign_in: Some(Some(SystemTime::UNIX_EPOCH + Duration::from_secs(5))),
+        ..UserPatch::default()
+    };
+    let json = serde_json::to_string(&patch).unwrap();
+    assert_eq!(
+        json,
+        r#"{"email":"bob@example.com","password":null,"last_sign_in":5}"#
+    );
+    assert_eq!(serde_json::from_str::<UserPatch>(&json).unwrap(), patch);
+    assert_eq!(
+        serde_json::from_str::<UserPatch>("{}").unwrap(),
+        UserPatch::default()
+    );
+    assert!(serde_json::from_str::<UserPatch>(r#"{"email":"nope"}"#).is_err());
+    // 状态不能靠补丁改
+    assert!(serde_json::from_str::<UserPatch>(r#"{"status":"active"}"#).is_err());
+}
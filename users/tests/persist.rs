@@ -0,0 +1,153 @@
+//用户在 JSON、TOML、CSV 之间往返不丢字段；保存是原子的，几个线程同时保存也不冲突，读到不合法的数据会报错，
+//按别的用户名策略起的名字也能读回来。
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use users::borrowed::parse_csv;
+use users::persist::{self, Format};
+use users::{
+    build_user, Kdf, PersistError, SqliteStorage, Storage, User, UserBuilder, UserRegistry,
+    UsernamePolicy,
+};
+
+fn users() -> Vec<User> {
+    let mut alice = build_user(
+        "alice@example.com".parse().unwrap(),
+        "alice".parse().unwrap(),
+    );
+    alice
+        .set_password(&Kdf::new(64, 1, 1).unwrap(), "correct horse")
+        .unwrap();
+    alice.sign_in_count = 7;
+    alice.last_sign_in = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    let mut bob = build_user(
+        "bob@bücher.example".parse().unwrap(),
+        "bob".parse().unwrap(),
+    );
+    bob.suspend("spam", SystemTime::UNIX_EPOCH + Duration::from_secs(60))
+        .unwrap();
+    vec![alice, bob]
+}
+
+fn dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("persist")
+        .join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn round_trips_through_every_format() {
+    let users = users();
+    for format in [Format::Json, Format::Toml, Format::Csv] {
+        let text = persist::to_string(&users, format).unwrap();
+        assert_eq!(
+            persist::from_str(&text, format).unwrap(),
+            users,
+            "{format:?}:\n{text}"
+        );
+    }
+
+    let csv = persist::to_string(&users, Format::Csv).unwrap();
+    let header = csv.lines().next().unwrap();
+    assert_eq!(
+        header,
+        "version,status,username,email,sign_in_count,password,last_sign_in,failed_sign_ins,\
+         locked_until,activated_at,suspended_at,deleted_at"
+    );
+    let toml = persist::to_string(&users, Format::Toml).unwrap();
+    assert!(toml.starts_with("[[users]]\nversion = 4\n"), "{toml}");
+}
+
+#[test]
+fn save_and_load_files_atomically() {
+    let dir = dir("save");
+    let registry = UserRegistry::from_users(users()).unwrap();
+    let users: Vec<User> = registry.iter().map(|(_, user)| user.clone()).collect();
+    for name in ["users.json", "users.toml", "users.csv"] {
+        let path = dir.join(name);
+        persist::save(&path, &users).unwrap();
+        persist::save(&path, &users).unwrap();
+        assert_eq!(persist::load(&path).unwrap(), users);
+    }
+    // 临时文件都改名走了
+    let leftovers: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name.to_string_lossy().ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "{leftovers:?}");
+
+    assert!(matches!(
+        persist::save(&dir.join("users.yaml"), &users),
+        Err(PersistError::UnknownFormat(_))
+    ));
+}
+
+#[test]
+fn concurrent_saves_do_not_share_a_temp_file() {
+    let dir = dir("concurrent");
+    let path = dir.join("users.json");
+    let users = users();
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    persist::save(&path, &users).unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(persist::load(&path).unwrap(), users);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+#[test]
+fn invalid_records_are_rejected() {
+    let json = r#"[{"status": "active", "username": "alice", "email": "not an email",
+        "sign_in_count": 1, "password": null, "last_sign_in": null,
+        "failed_sign_ins": 0, "locked_until": null}]"#;
+    let err = persist::from_str(json, Format::Json).unwrap_err();
+    assert!(
+        err.to_string().contains("email address has no `@`"),
+        "{err}"
+    );
+
+    let csv =
+        "status,username,email,sign_in_count,password,last_sign_in,failed_sign_ins,locked_until,\
+         activated_at,suspended_at,deleted_at\n\
+         frozen,alice,alice@example.com,1,,,0,,,,\n";
+    let err = persist::from_str(csv, Format::Csv).unwrap_err();
+    assert!(
+        err.to_string().contains("unknown account status `frozen`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn names_from_a_custom_policy_load_back() {
+    let lenient = UsernamePolicy {
+        min_len: 1,
+        ..UsernamePolicy::default()
+    };
+    let user = UserBuilder::new()
+        .policy(lenient)
+        .email("x@example.com")
+        .username("x")
+        .build()
+        .unwrap();
+    let users = vec![user];
+    for format in [Format::Json, Format::Toml, Format::Csv] {
+        let text = persist::to_string(&users, format).unwrap();
+        assert_eq!(persist::from_str(&text, format).unwrap(), users);
+    }
+    let csv = persist::to_string(&users, Format::Csv).unwrap();
+    let user = parse_csv(&csv).unwrap().next().unwrap().unwrap();
+    assert_eq!(user.to_owned().unwrap(), users[0]);
+
+    let mut storage = SqliteStorage::open_in_memory().unwrap();
+    let id = storage.insert(&users[0]).unwrap();
+    assert_eq!(storage.get(id).unwrap().unwrap(), users[0]);
+}
@@ -0,0 +1,121 @@
+//用户名和邮箱在整个注册表里唯一，邮箱不区分大小写；冲突的修改不会留下半截状态。
+
+use users::{build_user, Email, RegistryError, UserId, UserRegistry, Username};
+
+fn email(address: &str) -> Email {
+    address.parse().unwrap()
+}
+
+fn username(name: &str) -> Username {
+    name.parse().unwrap()
+}
+
+fn registry() -> (UserRegistry, UserId, UserId) {
+    let mut registry = UserRegistry::new();
+    let alice = registry
+        .create(build_user(email("Alice@Example.com"), username("alice")))
+        .unwrap();
+    let bob = registry
+        .create(build_user(email("bob@example.com"), username("bob")))
+        .unwrap();
+    (registry, alice, bob)
+}
+
+#[test]
+fn create_and_look_up() {
+    let (registry, alice, bob) = registry();
+    assert_eq!(registry.len(), 2);
+    assert_ne!(alice, bob);
+    assert_eq!(registry.find_by_username("alice").unwrap().0, alice);
+    assert_eq!(
+        registry.find_by_email("alice@example.COM").unwrap().0,
+        alice
+    );
+    assert_eq!(registry.get(bob).unwrap().username.as_str(), "bob");
+    assert!(registry.find_by_username("Alice").is_none());
+}
+
+#[test]
+fn duplicates_are_refused() {
+    let (mut registry, _, _) = registry();
+    assert_eq!(
+        registry.create(build_user(email("carol@example.com"), username("alice"))),
+        Err(RegistryError::DuplicateUsername("alice".into()))
+    );
+    assert_eq!(
+        registry.create(build_user(email("ALICE@example.com"), username("carol"))),
+        Err(RegistryError::DuplicateEmail("ALICE@example.com".into()))
+    );
+    assert_eq!(registry.len(), 2);
+}
+
+#[test]
+fn lookalike_usernames_are_refused() {
+    let (mut registry, alice, bob) = registry();
+    // 第一个字母是西里尔字母 а
+    let homoglyph = "\u{430}lice";
+    assert_eq!(
+        registry.create(build_user(email("carol@example.com"), username(homoglyph))),
+        Err(RegistryError::ConfusableUsername {
+            username: homoglyph.into(),
+            existing: "alice".into(),
+        })
+    );
+    assert!(registry
+        .update(bob, |user| user.username = username(homoglyph))
+        .is_err());
+    assert_eq!(registry.len(), 2);
+
+    // 改自己的名字不算撞脸
+    registry
+        .update(alice, |user| user.username = username(homoglyph))
+        .unwrap();
+}
+
+#[test]
+fn update_keeps_indexes_in_sync() {
+    let (mut registry, alice, bob) = registry();
+    let renamed = registry
+        .update(alice, |user| {
+            user.username = username("alicia");
+            user.email = email("alicia@example.com");
+        })
+        .unwrap();
+    assert_eq!(renamed.username.as_str(), "alicia");
+    assert!(registry.find_by_username("alice").is_none());
+    assert!(registry.find_by_email("alice@example.com").is_none());
+    assert_eq!(
+        registry.find_by_email("Alicia@example.com").unwrap().0,
+        alice
+    );
+
+    // 改成别人的邮箱：拒绝，原账户不变
+    let err = registry
+        .update(bob, |user| user.email = email("ALICIA@example.com"))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RegistryError::DuplicateEmail("ALICIA@example.com".into())
+    );
+    assert_eq!(registry.get(bob).unwrap().email.as_str(), "bob@example.com");
+
+    // 只改大小写不算和自己冲突
+    registry
+        .update(bob, |user| user.email = email("Bob@example.com"))
+        .unwrap();
+}
+
+#[test]
+fn remove_frees_username_and_email() {
+    let (mut registry, alice, _) = registry();
+    let removed = registry.remove(alice).unwrap();
+    assert_eq!(removed.username.as_str(), "alice");
+    assert_eq!(registry.remove(alice), Err(RegistryError::NotFound(alice)));
+    assert_eq!(
+        registry.update(alice, |_| {}).unwrap_err().to_string(),
+        format!("no user {alice}")
+    );
+    registry
+        .create(build_user(email("alice@example.com"), username("alice")))
+        .unwrap();
+}
@@ -0,0 +1,155 @@
+//会话：令牌随机且不透明，闲置过久或者活满三小时都会过期，可以单个撤销或者全部撤销，账户停用后作废。
+
+use std::time::{Duration, SystemTime};
+
+use users::session::THREE_HOURS_IN_SECONDS;
+use users::{
+    build_user, Clock, ManualClock, SessionError, SessionPolicy, SessionStore, UserId, UserRegistry,
+};
+
+const ALICE: UserId = UserId(1);
+const BOB: UserId = UserId(2);
+
+fn registry() -> UserRegistry {
+    let users = ["alice", "bob"].map(|name| {
+        build_user(
+            format!("{name}@example.com").parse().unwrap(),
+            name.parse().unwrap(),
+        )
+    });
+    UserRegistry::from_users(users).unwrap()
+}
+
+fn minutes(n: u64) -> Duration {
+    Duration::from_secs(n * 60)
+}
+
+#[test]
+fn tokens_are_opaque_and_unique() {
+    let clock = ManualClock::default();
+    let registry = registry();
+    let mut store = SessionStore::new(SessionPolicy::default(), &clock);
+    let first = store.issue(&registry, ALICE).unwrap();
+    let second = store.issue(&registry, ALICE).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(first.as_str().len(), 64);
+    assert!(first.as_str().chars().all(|c| c.is_ascii_hexdigit()));
+    assert!(!format!("{first:?}").contains(first.as_str()));
+
+    assert_eq!(
+        store.validate(&registry, first.as_str()).unwrap().user,
+        ALICE
+    );
+    assert_eq!(
+        store.validate(&registry, "not-a-token"),
+        Err(SessionError::NotFound)
+    );
+}
+
+#[test]
+fn idle_expiry_slides_but_absolute_expiry_does_not() {
+    let policy = SessionPolicy::default();
+    assert_eq!(policy.absolute.as_secs(), u64::from(THREE_HOURS_IN_SECONDS));
+
+    let clock = ManualClock::default();
+    let registry = registry();
+    let mut store = SessionStore::new(policy, &clock);
+    let token = store.issue(&registry, ALICE).unwrap();
+    let created = store.validate(&registry, token.as_str()).unwrap().created;
+
+    // 每 20 分钟用一次，闲置期一直往后推
+    for _ in 0..8 {
+        clock.advance(minutes(20));
+        store.validate(&registry, token.as_str()).unwrap();
+    }
+    // 2 小时 40 分钟，再 20 分钟就满三小时
+    clock.advance(minutes(20));
+    assert_eq!(
+        store.validate(&registry, token.as_str()),
+        Err(SessionError::Expired {
+            at: created + policy.absolute
+        })
+    );
+    assert_eq!(
+        store.validate(&registry, token.as_str()),
+        Err(SessionError::NotFound)
+    );
+
+    let idle = store.issue(&registry, ALICE).unwrap();
+    clock.advance(minutes(30));
+    assert!(matches!(
+        store.validate(&registry, idle.as_str()),
+        Err(SessionError::Expired { .. })
+    ));
+}
+
+#[test]
+fn unbounded_lifetimes_do_not_overflow() {
+    let policy = SessionPolicy {
+        idle: Duration::MAX,
+        absolute: Duration::MAX,
+    };
+    let clock = ManualClock::default();
+    let registry = registry();
+    let mut store = SessionStore::new(policy, &clock);
+    let token = store.issue(&registry, ALICE).unwrap();
+    clock.advance(Duration::from_secs(u64::from(u32::MAX)));
+    let session = store.validate(&registry, token.as_str()).unwrap();
+    assert!(session.expires > clock.now());
+}
+
+#[test]
+fn revoke_one_or_everywhere() {
+    let clock = ManualClock::default();
+    let registry = registry();
+    let mut store = SessionStore::new(SessionPolicy::default(), &clock);
+    let laptop = store.issue(&registry, ALICE).unwrap();
+    let phone = store.issue(&registry, ALICE).unwrap();
+    let bobs = store.issue(&registry, BOB).unwrap();
+
+    assert!(store.revoke(laptop.as_str()));
+    assert!(!store.revoke(laptop.as_str()));
+    assert_eq!(store.sessions_for(ALICE).count(), 1);
+
+    assert_eq!(store.revoke_all(ALICE), 1);
+    assert_eq!(
+        store.validate(&registry, phone.as_str()),
+        Err(SessionError::NotFound)
+    );
+    assert!(store.validate(&registry, bobs.as_str()).is_ok());
+
+    clock.advance(minutes(31));
+    assert_eq!(store.sessions_for(BOB).count(), 0);
+    assert_eq!(store.purge_expired(), 1);
+}
+
+#[test]
+fn only_active_users_keep_sessions() {
+    let clock = ManualClock::default();
+    let mut registry = registry();
+    let mut store = SessionStore::new(SessionPolicy::default(), &clock);
+    assert_eq!(
+        store.issue(&registry, UserId(7)),
+        Err(SessionError::UnknownUser(UserId(7)))
+    );
+
+    let token = store.issue(&registry, ALICE).unwrap();
+    registry
+        .update(ALICE, |user| {
+            user.suspend("spam", SystemTime::UNIX_EPOCH).unwrap()
+        })
+        .unwrap();
+    assert_eq!(
+        store.validate(&registry, token.as_str()),
+        Err(SessionError::Inactive)
+    );
+    assert_eq!(store.sessions_for(ALICE).count(), 0);
+    assert_eq!(store.issue(&registry, ALICE), Err(SessionError::Inactive));
+
+    let token = store.issue(&registry, BOB).unwrap();
+    registry.remove(BOB).unwrap();
+    assert_eq!(
+        store.validate(&registry, token.as_str()),
+        Err(SessionError::Inactive)
+    );
+}
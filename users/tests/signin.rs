@@ -0,0 +1,129 @@
+//登录：成功才计数，失败后退避时间翻倍，连续失败到阈值就锁定，停用的账户不能登录。
+
+use std::time::{Duration, SystemTime};
+
+use users::{
+    build_user, Authenticator, Clock, Kdf, ManualClock, SignInError, SignInPolicy, User,
+    UserRegistry,
+};
+
+const PASSWORD: &str = "correct horse";
+
+fn user(kdf: &Kdf) -> User {
+    let mut user = build_user(
+        "someone@example.com".parse().unwrap(),
+        "someone".parse().unwrap(),
+    );
+    user.set_password(kdf, PASSWORD).unwrap();
+    user
+}
+
+fn authenticator(clock: &ManualClock) -> Authenticator<&ManualClock> {
+    Authenticator::new(Kdf::new(64, 1, 1).unwrap(), SignInPolicy::default(), clock)
+}
+
+#[test]
+fn success_counts_and_records_time() {
+    let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000));
+    let auth = authenticator(&clock);
+    let mut user = user(&auth.kdf);
+    auth.sign_in(&mut user, PASSWORD).unwrap();
+    assert_eq!(user.sign_in_count, 2);
+    assert_eq!(user.last_sign_in, Some(clock.now()));
+
+    user.suspend("spam", clock.now()).unwrap();
+    assert_eq!(
+        auth.sign_in(&mut user, PASSWORD),
+        Err(SignInError::Inactive)
+    );
+    assert_eq!(user.sign_in_count, 2);
+}
+
+#[test]
+fn failures_back_off_exponentially_then_lock_out() {
+    let policy = SignInPolicy::default();
+    let delays: Vec<u64> = (1..=6).map(|n| policy.delay(n).as_secs()).collect();
+    assert_eq!(delays, [1, 2, 4, 8, 900, 900]);
+
+    let clock = ManualClock::default();
+    let auth = authenticator(&clock);
+    let mut user = user(&auth.kdf);
+
+    assert_eq!(
+        auth.sign_in(&mut user, "wrong"),
+        Err(SignInError::InvalidCredentials)
+    );
+    // 退避期间连正确的密码也不接受
+    assert_eq!(
+        auth.sign_in(&mut user, PASSWORD),
+        Err(SignInError::Throttled {
+            retry_after: Duration::from_secs(1)
+        })
+    );
+    for failures in 2..=5 {
+        clock.advance(policy.delay(failures - 1));
+        assert_eq!(
+            auth.sign_in(&mut user, "wrong"),
+            Err(SignInError::InvalidCredentials)
+        );
+        assert_eq!(user.failed_sign_ins, failures);
+    }
+    let until = user.locked_until.unwrap();
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(
+        auth.sign_in(&mut user, PASSWORD),
+        Err(SignInError::LockedOut { until })
+    );
+
+    // 锁定期过后重新开始计数
+    clock.advance(policy.lockout);
+    auth.sign_in(&mut user, PASSWORD).unwrap();
+    assert_eq!((user.failed_sign_ins, user.locked_until), (0, None));
+    assert_eq!(user.sign_in_count, 2);
+}
+
+#[test]
+fn registry_sign_in_persists_attempts() {
+    let clock = ManualClock::default();
+    let auth = authenticator(&clock);
+    let mut registry = UserRegistry::new();
+    let id = registry.create(user(&auth.kdf)).unwrap();
+
+    assert_eq!(
+        auth.sign_in_username(&mut registry, "nobody", PASSWORD),
+        Err(SignInError::InvalidCredentials)
+    );
+    assert_eq!(
+        auth.sign_in_username(&mut registry, "someone", "wrong"),
+        Err(SignInError::InvalidCredentials)
+    );
+    assert_eq!(registry.get(id).unwrap().failed_sign_ins, 1);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(
+        auth.sign_in_username(&mut registry, "someone", PASSWORD),
+        Ok(id)
+    );
+    assert_eq!(registry.get(id).unwrap().sign_in_count, 2);
+}
+
+#[test]
+fn huge_lockouts_do_not_overflow() {
+    let clock = ManualClock::default();
+    let policy = SignInPolicy {
+        lockout_threshold: 1,
+        lockout: Duration::MAX,
+        ..SignInPolicy::default()
+    };
+    let auth = Authenticator::new(Kdf::new(64, 1, 1).unwrap(), policy, &clock);
+    let mut user = user(&auth.kdf);
+    assert_eq!(
+        auth.sign_in(&mut user, "wrong"),
+        Err(SignInError::InvalidCredentials)
+    );
+    let until = user.locked_until.unwrap();
+    assert!(until > clock.now());
+    assert_eq!(
+        auth.sign_in(&mut user, PASSWORD),
+        Err(SignInError::LockedOut { until })
+    );
+}
@@ -0,0 +1,90 @@
+//账户状态只能按规定的路线转移，每次转移记下时间；不允许的转移报错且不改状态。
+
+use std::time::{Duration, SystemTime};
+
+use users::{build_user, Status, TransitionError, User};
+
+fn at(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn pending() -> User {
+    let mut user = build_user(
+        "alice@example.com".parse().unwrap(),
+        "alice".parse().unwrap(),
+    );
+    user.status = Status::Pending;
+    user
+}
+
+#[test]
+fn full_lifecycle_records_timestamps() {
+    let mut user = pending();
+    assert!(!user.is_active());
+    user.activate(at(10)).unwrap();
+    assert!(user.is_active());
+    user.suspend("chargeback", at(20)).unwrap();
+    assert_eq!(user.status.to_string(), "suspended: chargeback");
+    assert!(!user.is_active());
+    user.activate(at(30)).unwrap();
+    user.delete(at(40)).unwrap();
+
+    assert_eq!(user.status, Status::Deleted);
+    assert_eq!(
+        (user.activated_at, user.suspended_at, user.deleted_at),
+        (Some(at(30)), Some(at(20)), Some(at(40)))
+    );
+}
+
+#[test]
+fn illegal_moves_are_refused() {
+    let mut user = pending();
+    assert_eq!(
+        user.suspend("spam", at(1)),
+        Err(TransitionError {
+            from: Status::Pending,
+            to: Status::Suspended {
+                reason: "spam".into()
+            },
+        })
+    );
+    assert_eq!(user.status, Status::Pending);
+    assert_eq!(user.suspended_at, None);
+
+    user.activate(at(1)).unwrap();
+    let err = user.activate(at(2)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot change account status from active to active"
+    );
+    assert_eq!(user.activated_at, Some(at(1)));
+
+    // 任何状态都能删除，删除之后哪里都去不了
+    user.delete(at(3)).unwrap();
+    assert!(user.activate(at(4)).is_err());
+    assert!(user.delete(at(4)).is_err());
+    assert_eq!(user.deleted_at, Some(at(3)));
+}
+
+#[test]
+fn status_round_trips_through_strings() {
+    for status in [
+        Status::Pending,
+        Status::Active,
+        Status::Suspended {
+            reason: "too many reports".into(),
+        },
+        Status::Suspended {
+            reason: "  indented".into(),
+        },
+        Status::Suspended {
+            reason: String::new(),
+        },
+        Status::Deleted,
+    ] {
+        assert_eq!(status.to_string().parse::<Status>().unwrap(), status);
+        let json = serde_json::to_string(&status).unwrap();
+        assert_eq!(serde_json::from_str::<Status>(&json).unwrap(), status);
+    }
+    assert!("frozen".parse::<Status>().is_err());
+}
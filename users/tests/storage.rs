@@ -0,0 +1,309 @@
+//同一组行为测试同时跑在内存和 SQLite 两种存储上：增删改查、唯一性、事务回滚；SQLite 文件的版本升级。
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use users::{
+    build_user, Email, MemoryStorage, RegistryError, SqliteStorage, Status, Storage, StorageError,
+    User, UserId,
+};
+
+fn email(address: &str) -> Email {
+    address.parse().unwrap()
+}
+
+fn user(name: &str, address: &str) -> User {
+    build_user(email(address), name.parse().unwrap())
+}
+
+fn sqlite() -> SqliteStorage {
+    SqliteStorage::open_in_memory().unwrap()
+}
+
+fn create_read_update_delete(mut storage: impl Storage) {
+    let mut alice = user("alice", "Alice@Example.com");
+    alice.sign_in_count = 4;
+    alice.last_sign_in = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    let a = storage.insert(&alice).unwrap();
+    let b = storage.insert(&user("bob", "bob@bücher.example")).unwrap();
+    assert_eq!((a, b), (UserId(1), UserId(2)));
+
+    assert_eq!(storage.get(a).unwrap(), Some(alice.clone()));
+    assert_eq!(storage.find_by_username("bob").unwrap().unwrap().0, b);
+    assert!(storage.find_by_username("Bob").unwrap().is_none());
+    let found = storage.find_by_email(&email("alice@example.COM")).unwrap();
+    assert_eq!(found.unwrap().0, a);
+
+    alice
+        .suspend("spam", SystemTime::UNIX_EPOCH + Duration::from_secs(60))
+        .unwrap();
+    alice.email = email("alice@example.org");
+    storage.update(a, &alice).unwrap();
+    assert_eq!(storage.get(a).unwrap(), Some(alice.clone()));
+    assert!(storage
+        .find_by_email(&email("alice@example.com"))
+        .unwrap()
+        .is_none());
+
+    assert_eq!(storage.remove(a).unwrap(), alice);
+    assert_eq!(storage.get(a).unwrap(), None);
+    assert_eq!(storage.list().unwrap().len(), 1);
+    assert_eq!(
+        storage.remove(a),
+        Err(StorageError::Registry(RegistryError::NotFound(a)))
+    );
+}
+
+#[test]
+fn crud() {
+    create_read_update_delete(MemoryStorage::new());
+    create_read_update_delete(sqlite());
+}
+
+fn unique_username_and_email(mut storage: impl Storage) {
+    let alice = storage.insert(&user("alice", "alice@example.com")).unwrap();
+    let bob = storage.insert(&user("bob", "bob@example.com")).unwrap();
+
+    assert_eq!(
+        storage.insert(&user("alice", "other@example.com")),
+        Err(StorageError::Registry(RegistryError::DuplicateUsername(
+            "alice".into()
+        )))
+    );
+    assert!(matches!(
+        storage.insert(&user("carol", "ALICE@example.com")),
+        Err(StorageError::Registry(RegistryError::DuplicateEmail(_)))
+    ));
+    assert!(matches!(
+        storage.insert(&user("\u{430}lice", "carol@example.com")),
+        Err(StorageError::Registry(
+            RegistryError::ConfusableUsername { .. }
+        ))
+    ));
+    // 改名撞上别人时原账户不变
+    assert!(storage
+        .update(bob, &user("alice", "bob@example.com"))
+        .is_err());
+    assert_eq!(
+        storage.update(bob, &user("\u{430}lice", "bob@example.com")),
+        Err(StorageError::Registry(RegistryError::ConfusableUsername {
+            username: "\u{430}lice".into(),
+            existing: "alice".into(),
+        }))
+    );
+    assert_eq!(storage.get(bob).unwrap().unwrap().username.as_str(), "bob");
+    // 不改名的更新不和自己冲突
+    storage
+        .update(bob, &user("bob", "Bob@example.com"))
+        .unwrap();
+    let ids: Vec<_> = storage
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    assert_eq!(ids, [alice, bob]);
+}
+
+#[test]
+fn uniqueness() {
+    unique_username_and_email(MemoryStorage::new());
+    unique_username_and_email(sqlite());
+}
+
+fn failed_transactions_roll_back(mut storage: impl Storage) {
+    storage.insert(&user("alice", "alice@example.com")).unwrap();
+
+    let result: Result<(), _> = storage.transaction(|storage| {
+        storage.insert(&user("bob", "bob@example.com"))?;
+        Err(StorageError::Backend("abort".into()))
+    });
+    assert!(result.is_err());
+    assert_eq!(storage.list().unwrap().len(), 1);
+
+    // 第二个和已有账户冲突，第一个也不留下
+    let result = storage.transaction(|storage| {
+        storage.insert(&user("bob", "bob@example.com"))?;
+        storage.insert(&user("alice", "a2@example.com"))
+    });
+    assert!(result.is_err());
+    assert!(storage.find_by_username("bob").unwrap().is_none());
+
+    // 嵌套事务：里层失败只撤销里层
+    storage
+        .transaction(|storage| {
+            storage.insert(&user("bob", "bob@example.com"))?;
+            let inner: Result<(), _> = storage.transaction(|storage| {
+                storage.insert(&user("carol", "carol@example.com"))?;
+                Err(StorageError::Backend("abort".into()))
+            });
+            assert!(inner.is_err());
+            Ok(())
+        })
+        .unwrap();
+    let names: Vec<_> = storage
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|(_, user)| user.username.to_string())
+        .collect();
+    assert_eq!(names, ["alice", "bob"]);
+}
+
+#[test]
+fn transactions() {
+    failed_transactions_roll_back(MemoryStorage::new());
+    failed_transactions_roll_back(sqlite());
+}
+
+#[test]
+fn sqlite_rolls_back_when_a_transaction_panics() {
+    let mut storage = sqlite();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _: Result<(), _> = storage.transaction(|storage| {
+            storage.insert(&user("bob", "bob@example.com"))?;
+            panic!("abort");
+        });
+    }));
+    assert!(result.is_err());
+    assert!(storage.list().unwrap().is_empty());
+    storage.insert(&user("bob", "bob@example.com")).unwrap();
+}
+
+/// 存储测试用的数据库文件，先删掉上次留下的
+fn database(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("storage");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn set_schema_version(path: &Path, version: u32) {
+    let conn = rusqlite::Connection::open(path).unwrap();
+    conn.pragma_update(None, "user_version", version).unwrap();
+}
+
+#[test]
+fn sqlite_refuses_unknown_schema_versions() {
+    let path = database("users-future.sqlite");
+    SqliteStorage::open(&path).unwrap();
+    set_schema_version(&path, 9);
+    let err = SqliteStorage::open(&path).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "storage error: database schema version 9 is newer than 5"
+    );
+    // 没有被改回当前版本
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let version: u32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, 9);
+
+    set_schema_version(&path, 2);
+    let err = SqliteStorage::open(&path).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "storage error: cannot upgrade database schema version 2"
+    );
+}
+
+#[test]
+fn sqlite_file_survives_reopening() {
+    let path = database("users.sqlite");
+
+    let alice = user("alice", "alice@example.com");
+    let id = SqliteStorage::open(&path).unwrap().insert(&alice).unwrap();
+    let mut storage = SqliteStorage::open(&path).unwrap();
+    assert_eq!(storage.get(id).unwrap(), Some(alice));
+    assert!(storage.insert(&user("alice", "x@example.com")).is_err());
+}
+
+#[test]
+fn sqlite_upgrades_version_3_files() {
+    let path = database("users-v3.sqlite");
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT, active INTEGER NOT NULL,
+                 username TEXT NOT NULL, email TEXT NOT NULL, email_key TEXT NOT NULL,
+                 sign_in_count INTEGER NOT NULL, password TEXT, last_sign_in INTEGER,
+                 failed_sign_ins INTEGER NOT NULL, locked_until INTEGER);
+             INSERT INTO users (active, username, email, email_key, sign_in_count, failed_sign_ins)
+             VALUES (1, 'alice', 'alice@example.com', 'alice@example.com', 2, 0),
+                    (0, 'bob', 'bob@example.com', 'bob@example.com', 1, 0);
+             PRAGMA user_version = 3;",
+        )
+        .unwrap();
+    }
+
+    let storage = SqliteStorage::open(&path).unwrap();
+    let users = storage.list().unwrap();
+    assert!(users[0].1.is_active());
+    assert_eq!(users[0].1.sign_in_count, 2);
+    assert_eq!(
+        users[1].1.status,
+        Status::Suspended {
+            reason: "deactivated".into()
+        }
+    );
+}
+
+#[test]
+fn sqlite_upgrades_version_4_files() {
+    let path = database("users-v4.sqlite");
+    let create = "CREATE TABLE users (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT, status TEXT NOT NULL,
+                      username TEXT NOT NULL, email TEXT NOT NULL, email_key TEXT NOT NULL,
+                      sign_in_count INTEGER NOT NULL, password TEXT, last_sign_in INTEGER,
+                      failed_sign_ins INTEGER NOT NULL, locked_until INTEGER,
+                      activated_at INTEGER, suspended_at INTEGER, deleted_at INTEGER);
+                  PRAGMA user_version = 4;";
+    let insert = |conn: &rusqlite::Connection, name: &str| {
+        conn.execute(
+            "INSERT INTO users (status, username, email, email_key, sign_in_count,
+                                failed_sign_ins)
+             VALUES ('active', ?1, ?1 || '@example.com', ?1 || '@example.com', 0, 0)",
+            [name],
+        )
+        .unwrap();
+    };
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(create).unwrap();
+        insert(&conn, "alice");
+        insert(&conn, "bob");
+    }
+
+    // 升级时补上骨架，之后照样拦下看起来一样的名字
+    let mut storage = SqliteStorage::open(&path).unwrap();
+    assert_eq!(storage.list().unwrap().len(), 2);
+    assert!(matches!(
+        storage.insert(&user("\u{430}lice", "carol@example.com")),
+        Err(StorageError::Registry(
+            RegistryError::ConfusableUsername { .. }
+        ))
+    ));
+
+    // 已有的名字看起来一样时没法升级，文件保持原样
+    let path = database("users-v4-lookalikes.sqlite");
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(create).unwrap();
+        insert(&conn, "alice");
+        insert(&conn, "\u{430}lice");
+    }
+    let err = SqliteStorage::open(&path).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "storage error: usernames `alice` and `\u{430}lice` look alike, rename one before upgrading"
+    );
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let version: u32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, 4);
+}
@@ -0,0 +1,93 @@
+//用户名规则：每一条违反的规则都单独报告，和保留名长得一样的名字也会被拦下。
+
+use users::{CharClass, Username, UsernamePolicy, Violation};
+
+#[test]
+fn default_policy_accepts_ordinary_names() {
+    for name in ["alice", "bob_42", "José", "李小龙", "first.last-2"] {
+        assert!(Username::parse(name).is_ok(), "{name}");
+    }
+}
+
+#[test]
+fn every_violated_rule_is_reported() {
+    let err = Username::parse(" a!").unwrap_err();
+    assert_eq!(
+        err.violations,
+        [
+            Violation::SurroundingWhitespace,
+            Violation::DisallowedChars(vec!['!']),
+        ]
+    );
+
+    let err = Username::parse(" x y ").unwrap_err();
+    assert_eq!(
+        err.violations,
+        [
+            Violation::SurroundingWhitespace,
+            Violation::DisallowedChars(vec![' ']),
+        ]
+    );
+
+    let err = Username::parse("a$").unwrap_err();
+    assert_eq!(
+        err.violations,
+        [
+            Violation::TooShort { min: 3, len: 2 },
+            Violation::DisallowedChars(vec!['$']),
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid username \"a$\": must be at least 3 characters, got 2; \
+         contains disallowed characters '$'"
+    );
+}
+
+#[test]
+fn reserved_and_confusable_names() {
+    let policy = UsernamePolicy::default();
+    assert_eq!(
+        policy.violations("Admin"),
+        [Violation::Reserved("admin".into())]
+    );
+    // 第一个字母是西里尔字母 а（U+0430）
+    assert_eq!(
+        policy.violations("\u{430}dmin"),
+        [Violation::Confusable("admin".into())]
+    );
+    assert_eq!(
+        policy.violations("rooT"),
+        [Violation::Reserved("root".into())]
+    );
+
+    let taken = ["paypal", "alice"];
+    assert_eq!(policy.confusable_with("pаypаl", taken), Some("paypal"));
+    assert_eq!(policy.confusable_with("paypal", taken), None);
+    assert_eq!(policy.confusable_with("bob", taken), None);
+}
+
+#[test]
+fn policy_is_configurable() {
+    let policy = UsernamePolicy {
+        min_len: 1,
+        max_len: 8,
+        allowed: vec![CharClass::AsciiLowercase, CharClass::AsciiDigit],
+        reserved: vec!["guest".into()],
+    };
+    assert!(policy.parse("admin").is_ok());
+    assert_eq!(
+        policy.violations("José_Garcia"),
+        [
+            Violation::TooLong { max: 8, len: 11 },
+            Violation::DisallowedChars(vec!['J', 'é', '_', 'G']),
+        ]
+    );
+    assert_eq!(
+        policy.violations("GUEST"),
+        [
+            Violation::DisallowedChars(vec!['G', 'U', 'E', 'S', 'T']),
+            Violation::Reserved("guest".into()),
+        ]
+    );
+}